@@ -5,6 +5,7 @@ use my_component_a::MyComponentA;
 use my_component_b::MyComponentB;
 use my_component_c::MyComponentC;
 use my_component_d::MyComponentD;
+use my_component_e::MyComponentE;
 
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
@@ -18,6 +19,7 @@ fn my_app() -> Html {
             <MyComponentA />
             <MyComponentC />
             <MyComponentD />
+            <MyComponentE />
         </>
     }
 }
@@ -0,0 +1,110 @@
+use yew::prelude::*;
+use yew_style_in_rs::dyn_style::DynStyle;
+use yew_style_in_rs::units::Px;
+use yew_style_in_rs::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Size {
+    Small,
+    Large,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Tone {
+    Primary,
+    Danger,
+}
+
+styled! {
+    /// A button with the size and the tone.
+    pub Button: button {
+        let css = css!(atomic) {r#"
+            border: none;
+            padding: 4px 8px;
+            &:hover {
+                opacity: 0.8;
+            }
+        "#};
+        let variant = variants! {
+            size: Size {
+                Size::Small => "font-size: 12px;",
+                Size::Large => "font-size: 16px;",
+            },
+            tone: Tone {
+                Tone::Primary => "color: white; background: royalblue;",
+                Tone::Danger => "color: white; background: crimson;",
+            },
+        };
+    }
+}
+
+styled! {
+    pub Link: a[href, target, oncontextmenu: MouseEvent] {
+        let css = css! {"color: royalblue;"};
+    }
+}
+
+styled! {
+    pub Bar: div(width: Px) {
+        let css = css! {"height: 20px; background: gray;"};
+        let dyn_css = dyn css! {r#"width: ${width};"#};
+    }
+}
+
+// Progress bar of a struct component with `dyn_style!`.
+pub struct Progress {
+    style: DynStyle,
+    width: u32,
+}
+impl Component for Progress {
+    type Message = ();
+    type Properties = ();
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        let width = 10;
+        Self {
+            style: dyn_style!(r#"width: ${width}%; & > span { color: white; }"#),
+            width,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, _msg: ()) -> bool {
+        self.width = (self.width + 10) % 110;
+        let width = self.width;
+        dyn_style!(
+            self.style,
+            r#"width: ${width}%; & > span { color: white; }"#
+        )
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let onclick = ctx.link().callback(|_| ());
+        html! {
+            <div class={&self.style} style="background: seagreen;" {onclick}>
+                <span>{format!("{}%", self.width)}</span>
+            </div>
+        }
+    }
+}
+
+#[function_component(MyComponentE)]
+pub fn my_component_e() -> Html {
+    let width = use_state(|| 40.0);
+    let onclick = Callback::from({
+        let width = width.clone();
+        move |_| width.set(if *width >= 200.0 { 40.0 } else { *width + 40.0 })
+    });
+    let oncontextmenu = Callback::from(|e: MouseEvent| e.prevent_default());
+
+    html! {
+        <div>
+            <Button size={Size::Large} tone={Tone::Primary} {onclick}>{"Grow"}</Button>
+            <Button size={Size::Small} tone={Tone::Danger} disabled=true>{"Disabled"}</Button>
+            <Bar width={Px(*width)} />
+            <Link href="https://github.com/MatchaChoco010/yew-style-in-rs" target="_blank" {oncontextmenu}>
+                {"yew-style-in-rs"}
+            </Link>
+            <Progress />
+        </div>
+    }
+}
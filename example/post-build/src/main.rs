@@ -16,16 +16,17 @@ fn main() {
     let staging_dir = env::var("TRUNK_STAGING_DIR").unwrap();
     let staging_dir = Path::new(&staging_dir);
 
-    // Copy *.css from the output directory to the staging directory
-    for entry in fs::read_dir(output_dir).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let ext = path.extension();
-        if let Some(ext) = ext {
-            if ext == "css" {
-                let dist_path = staging_dir.join(path.file_name().unwrap());
-                fs::copy(path, dist_path).unwrap();
-            }
+    // Collect the css fragments of the build into the css files of the output directory.
+    // Run from the workspace, so that the rules are ordered by the dependency graph.
+    env::set_current_dir(manifest_dir.parent().unwrap()).unwrap();
+    let written = yew_style_in_rs_build::collect(&output_dir).unwrap();
+
+    // Copy the css files of `my-app` to the staging directory
+    let app_dir = output_dir.join("yew-style-in-rs").join("my-app");
+    for path in written {
+        if path.parent() == Some(&app_dir) && path.extension().map_or(false, |ext| ext == "css") {
+            let dist_path = staging_dir.join(path.file_name().unwrap());
+            fs::copy(path, dist_path).unwrap();
         }
     }
 }
@@ -0,0 +1,289 @@
+// Configuration of the style pipeline.
+//
+// The configuration is read from `yew-style.toml` in the workspace root,
+// or `[workspace.metadata.yew-style-in-rs]` / `[package.metadata.yew-style-in-rs]`
+// in `Cargo.toml` of the workspace root.
+// Unknown keys are errors, so typos are not silently ignored.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Filename of the configuration file in the workspace root.
+pub const CONFIG_FILE: &str = "yew-style.toml";
+
+// Key of the configuration in the metadata table of `Cargo.toml`.
+const METADATA_KEY: &str = "yew-style-in-rs";
+
+/// Configuration of `yew-style-in-rs`.
+///
+/// ```toml
+/// # yew-style.toml
+/// out-dir = "dist/css"
+/// default-filename = "app"
+/// class-prefix = "ys-"
+/// pretty = true
+///
+/// [id]
+/// scheme = "hash"
+/// length = 10
+///
+/// [targets]
+/// safari = "14"
+///
+/// [[layers]]
+/// name = "components"
+/// crates = ["my-components"]
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    /// Output directory of the css files relative to the configuration root.
+    /// `YEW_STYLE_IN_RS_OUT_DIR` overrides it.
+    /// The CSS fragments stay in the profile directory.
+    pub out_dir: Option<PathBuf>,
+    /// Css filename without `.css` of the declarations without `filename`.
+    pub default_filename: String,
+    pub id: IdConfig,
+    /// Prefix of the class names of `css!` and the animation name ids of `keyframes!`.
+    pub class_prefix: String,
+    /// Remove the last semicolon of each block.
+    pub minify: bool,
+    /// Write the css files with newlines and indentation.
+    pub pretty: bool,
+    /// Minimum browser versions to add vendor prefixes for.
+    pub targets: BTreeMap<Browser, Version>,
+    /// Cascade layers in order with the crates in each layer.
+    pub layers: Vec<Layer>,
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            out_dir: None,
+            default_filename: "style".into(),
+            id: IdConfig::default(),
+            class_prefix: String::new(),
+            minify: false,
+            pretty: false,
+            targets: BTreeMap::new(),
+            layers: vec![],
+        }
+    }
+}
+impl Config {
+    /// Load the configuration of the workspace root.
+    ///
+    /// Returns the default configuration if there is no configuration.
+    pub fn load(workspace_root: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self::load_with_path(workspace_root)?.0)
+    }
+
+    /// Load the configuration of the workspace root with the path of the file it is read from.
+    ///
+    /// The path is `yew-style.toml`, or `Cargo.toml` only if it has the metadata of `yew-style-in-rs`.
+    /// Returns the default configuration and `None` if there is no configuration.
+    pub fn load_with_path(workspace_root: impl AsRef<Path>) -> Result<(Self, Option<PathBuf>)> {
+        let workspace_root = workspace_root.as_ref();
+
+        let config_path = workspace_root.join(CONFIG_FILE);
+        if config_path.exists() {
+            let content = fs::read_to_string(&config_path)?;
+            let config = Self::parse(&content).with_context(|| invalid(&config_path))?;
+            return Ok((config, Some(config_path)));
+        }
+
+        let manifest_path = workspace_root.join("Cargo.toml");
+        if manifest_path.exists() {
+            let content = fs::read_to_string(&manifest_path)?;
+            let manifest: toml::Value = toml::from_str(&content)?;
+            for table in ["workspace", "package"] {
+                let config = manifest
+                    .get(table)
+                    .and_then(|t| t.get("metadata"))
+                    .and_then(|m| m.get(METADATA_KEY));
+                if let Some(config) = config {
+                    let config = Self::from_value(config.clone())
+                        .with_context(|| invalid(&manifest_path))?;
+                    return Ok((config, Some(manifest_path)));
+                }
+            }
+        }
+
+        Ok((Self::default(), None))
+    }
+
+    /// Parse and validate the content of `yew-style.toml`.
+    pub fn parse(content: &str) -> Result<Self> {
+        Self::from_value(toml::from_str(content)?)
+    }
+
+    fn from_value(value: toml::Value) -> Result<Self> {
+        let config: Self = value.try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.default_filename.is_empty() || self.default_filename.contains(['/', '\\', '.']) {
+            anyhow::bail!(
+                "`default-filename` must be a filename without an extension: `{}`",
+                self.default_filename
+            );
+        }
+        if !self.class_prefix.is_empty() && !is_ident(&self.class_prefix) {
+            anyhow::bail!(
+                "`class-prefix` must be a css identifier: `{}`",
+                self.class_prefix
+            );
+        }
+        if !(IdConfig::MIN_LENGTH..=IdConfig::MAX_LENGTH).contains(&self.id.length) {
+            anyhow::bail!(
+                "`id.length` must be from {} to {}: {}",
+                IdConfig::MIN_LENGTH,
+                IdConfig::MAX_LENGTH,
+                self.id.length
+            );
+        }
+        if self.minify && self.pretty {
+            anyhow::bail!("`minify` and `pretty` can not be enabled together");
+        }
+
+        let mut names = HashSet::new();
+        let mut crates = HashSet::new();
+        for layer in &self.layers {
+            if !is_ident(&layer.name) {
+                anyhow::bail!("layer name must be a css identifier: `{}`", layer.name);
+            }
+            if !names.insert(&layer.name) {
+                anyhow::bail!("layer `{}` is declared twice", layer.name);
+            }
+            for crate_name in &layer.crates {
+                if !crates.insert(crate_name) {
+                    anyhow::bail!("crate `{crate_name}` is in more than one layer");
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Layer of the crate if any.
+    pub fn layer_of(&self, crate_name: &str) -> Option<&str> {
+        self.layers
+            .iter()
+            .find(|layer| layer.crates.iter().any(|c| c == crate_name))
+            .map(|layer| layer.name.as_str())
+    }
+}
+
+fn invalid(path: &Path) -> String {
+    format!(
+        "Invalid configuration of yew-style-in-rs in {}",
+        path.display()
+    )
+}
+
+// Check the name is a css identifier without escapes.
+fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(ch) if ch.is_ascii_alphabetic() || ch == '_' || ch == '-' => (),
+        _ => return false,
+    }
+    chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+}
+
+/// How the ids of `css!` and `keyframes!` are generated.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdConfig {
+    pub scheme: IdScheme,
+    /// Number of alphabetic characters of the id without `class-prefix`.
+    pub length: usize,
+}
+impl IdConfig {
+    pub const MIN_LENGTH: usize = 4;
+    pub const MAX_LENGTH: usize = 32;
+}
+impl Default for IdConfig {
+    fn default() -> Self {
+        Self {
+            scheme: IdScheme::Hash,
+            length: 8,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IdScheme {
+    /// Hash of the crate name, the module path, the declaration name and the code.
+    Hash,
+    /// Random characters, different on every build.
+    Random,
+}
+
+/// Browsers of `targets`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Browser {
+    Chrome,
+    Edge,
+    Firefox,
+    Safari,
+}
+
+/// Browser version like `15.4`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "VersionValue")]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+impl Version {
+    pub const fn new(major: u32, minor: u32) -> Self {
+        Self { major, minor }
+    }
+}
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+// Version written as `"15.4"`, `15.4` or `15`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum VersionValue {
+    String(String),
+    Integer(u32),
+    Float(f64),
+}
+impl TryFrom<VersionValue> for Version {
+    type Error = String;
+    fn try_from(value: VersionValue) -> Result<Self, Self::Error> {
+        let version = match value {
+            VersionValue::String(s) => s,
+            VersionValue::Integer(n) => n.to_string(),
+            VersionValue::Float(f) => f.to_string(),
+        };
+        let invalid = || format!("invalid browser version: `{version}`");
+        let (major, minor) = version.split_once('.').unwrap_or((&version, "0"));
+        Ok(Self {
+            major: major.parse().map_err(|_| invalid())?,
+            minor: minor.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// A cascade layer and the crates whose rules are in it.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Layer {
+    pub name: String,
+    #[serde(default)]
+    pub crates: Vec<String>,
+}
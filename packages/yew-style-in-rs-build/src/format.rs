@@ -0,0 +1,107 @@
+// Formatting of the output css.
+//
+// The css of fragments is already compact like `.AbCdEfGh{color:red;}`,
+// so only semicolons, newlines and indentation are changed.
+// Strings like `content: "{;}";` are kept as they are.
+
+// Remove the last semicolon of each block.
+//
+// eg)
+// .AbCdEfGh{color:red;} -> .AbCdEfGh{color:red}
+pub fn minify(css: &str) -> String {
+    let mut output = String::with_capacity(css.len());
+    let mut pending_semicolon = false;
+    for (ch, in_string) in chars(css) {
+        if pending_semicolon {
+            pending_semicolon = false;
+            if ch != '}' {
+                output.push(';');
+            }
+        }
+        if ch == ';' && !in_string {
+            pending_semicolon = true;
+        } else {
+            output.push(ch);
+        }
+    }
+    if pending_semicolon {
+        output.push(';');
+    }
+    output
+}
+
+// Write each declaration and rule on its own line with two space indentation.
+//
+// eg)
+// .AbCdEfGh{color:red;}
+// ->
+// .AbCdEfGh {
+//   color: red;
+// }
+pub fn pretty(css: &str) -> String {
+    let mut output = String::with_capacity(css.len() * 2);
+    let mut depth = 0;
+    let mut line = String::new();
+    for (ch, in_string) in chars(css) {
+        match ch {
+            _ if in_string => line.push(ch),
+            '{' => {
+                push_line(&mut output, line.trim().to_string() + " {", depth);
+                line.clear();
+                depth += 1;
+            }
+            ';' => {
+                push_line(&mut output, declaration(&line) + ";", depth);
+                line.clear();
+            }
+            '}' => {
+                // the last declaration of the block may have no semicolon
+                push_line(&mut output, declaration(&line), depth);
+                line.clear();
+                depth = depth.saturating_sub(1);
+                push_line(&mut output, "}".into(), depth);
+            }
+            _ => line.push(ch),
+        }
+    }
+    push_line(&mut output, declaration(&line), depth);
+    output
+}
+
+fn push_line(output: &mut String, line: String, depth: usize) {
+    if !line.is_empty() {
+        output.push_str(&"  ".repeat(depth));
+        output.push_str(&line);
+        output.push('\n');
+    }
+}
+
+// Add a space after the colon of a declaration like `color:red`.
+// Statements like `@layer a,b` are kept as they are.
+fn declaration(line: &str) -> String {
+    let line = line.trim();
+    match line.split_once(':') {
+        Some((property, value)) if !line.starts_with('@') => {
+            format!("{}: {}", property.trim(), value.trim())
+        }
+        _ => line.to_string(),
+    }
+}
+
+// Iterate characters with the flag whether the character is in a string.
+fn chars(css: &str) -> impl Iterator<Item = (char, bool)> + '_ {
+    let mut quote = None;
+    let mut escaped = false;
+    css.chars().map(move |ch| {
+        let in_string = quote.is_some();
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if ch == '\\' => escaped = true,
+            Some(q) if ch == q => quote = None,
+            Some(_) => (),
+            None if ch == '"' || ch == '\'' => quote = Some(ch),
+            None => (),
+        }
+        (ch, in_string || quote.is_some())
+    })
+}
@@ -0,0 +1,84 @@
+// CSS fragments written by `css!` and `keyframes!`.
+//
+// Each `css!` and `keyframes!` declaration is written to
+// `target/<profile>/build-yew-style-in-rs/<CRATE NAME>/<ID>`.
+//
+// The first line of a fragment is the output filename without `.css`.
+// The following lines are one of
+// - `@meta <json>`: `FragmentMeta` of the declaration
+// - `@atomic <rule>`: an atomic rule of the `atomic` mode
+// - css of the declaration
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+
+// Lines of CSS fragments starting with this prefix are atomic rules.
+// They are deduplicated across every crate and written before the scoped rules,
+// so that nested selectors and at-rules of the scoped class win over them.
+pub const ATOMIC_RULE_PREFIX: &str = "@atomic ";
+
+// The line of CSS fragments starting with this prefix is JSON of `FragmentMeta`.
+pub const META_PREFIX: &str = "@meta ";
+
+// Directory of CSS fragments in the profile directory.
+pub const FRAGMENT_DIR: &str = "build-yew-style-in-rs";
+
+// Source information of a CSS fragment written to `yew-style-manifest.json`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FragmentMeta {
+    // source file path relative to the package root
+    pub file: String,
+    pub line: usize,
+    // identifier of the declaration, or `keyframes`
+    pub name: String,
+    // scoped animation names of `keyframes!`
+    pub keyframes: Vec<String>,
+}
+impl FragmentMeta {
+    // Write the meta line into the CSS fragment.
+    pub fn write(&self, file: &mut fs::File) -> Result<()> {
+        let json = serde_json::to_string(self)?;
+        file.write_all(format!("{META_PREFIX}{json}\n").as_bytes())?;
+        Ok(())
+    }
+}
+
+// A parsed CSS fragment.
+pub(crate) struct Fragment {
+    pub filename: String,
+    pub meta: FragmentMeta,
+    pub atomic_rules: Vec<String>,
+    pub css: String,
+}
+impl Fragment {
+    pub fn parse(content: &str) -> Self {
+        let mut lines = content.lines();
+        let filename = lines.next().unwrap_or("style").to_string();
+        let mut meta = FragmentMeta::default();
+        let mut atomic_rules = vec![];
+        let mut css = String::new();
+        for line in lines {
+            if let Some(json) = line.strip_prefix(META_PREFIX) {
+                meta = serde_json::from_str(json).unwrap_or_default();
+            } else if let Some(rule) = line.strip_prefix(ATOMIC_RULE_PREFIX) {
+                atomic_rules.push(rule.to_string());
+            } else {
+                css.push_str(line);
+            }
+        }
+        Self {
+            filename,
+            meta,
+            atomic_rules,
+            css,
+        }
+    }
+
+    // byte size of the css of the declaration including its atomic rules
+    pub fn bytes(&self) -> usize {
+        self.atomic_rules.iter().map(String::len).sum::<usize>() + self.css.len()
+    }
+}
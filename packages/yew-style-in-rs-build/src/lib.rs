@@ -0,0 +1,437 @@
+//! Build-time collection of the css generated by `yew-style-in-rs`.
+//!
+//! `css!` and `keyframes!` write CSS fragments to `target/<profile>/build-yew-style-in-rs/`
+//! while the crates are compiled.
+//! Call [`collect`] once after the build, e.g. from a post-build step,
+//! to write `style.css`, other css files and `yew-style-manifest.json` to `target/<profile>/`.
+//!
+//! ```rust,no_run
+//! fn main() -> anyhow::Result<()> {
+//!     yew_style_in_rs_build::collect("target/release")?;
+//!     Ok(())
+//! }
+//! ```
+
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+mod config;
+mod format;
+mod fragment;
+mod metadata;
+
+// Used by `yew-style-in-rs-macro` to write fragments.
+#[doc(hidden)]
+pub use fragment::{FragmentMeta, ATOMIC_RULE_PREFIX, FRAGMENT_DIR, META_PREFIX};
+
+pub use config::{Browser, Config, IdConfig, IdScheme, Layer, Version, CONFIG_FILE};
+pub use metadata::{
+    cargo_build_graph, cargo_packages, cargo_workspace, App, BuildGraph, Workspace,
+};
+
+use fragment::Fragment;
+
+#[cfg(test)]
+mod tests;
+
+/// Environment variable to override the directory of the css files and `yew-style-manifest.json`,
+/// e.g. `YEW_STYLE_IN_RS_OUT_DIR=dist/css`.
+///
+/// A relative path is resolved from the configuration root.
+/// The CSS fragments stay in the profile directory.
+pub const OUT_DIR_ENV: &str = "YEW_STYLE_IN_RS_OUT_DIR";
+
+/// Environment variable to set the directory of the configuration,
+/// e.g. the root of the top-level workspace.
+///
+/// Without it, the configuration is read from the workspace of the crate being compiled,
+/// so crates from the registry or git read the configuration of their own workspace.
+/// Set an absolute path, e.g. with `relative = true` in `[env]` of `.cargo/config.toml`,
+/// because rustc runs in another directory for crates outside of the workspace.
+pub const CONFIG_DIR_ENV: &str = "YEW_STYLE_IN_RS_CONFIG_DIR";
+
+// Directory of the outputs of each application in the profile directory.
+// `<out_dir>/<app>` is the executable of a native `bin` application.
+const APP_DIR: &str = "yew-style-in-rs";
+
+/// Collect the CSS fragments in `<profile_dir>/build-yew-style-in-rs/`
+/// and write the css files and `yew-style-manifest.json` into `profile_dir`.
+///
+/// `profile_dir` is the profile directory like `target/release`.
+/// If `cargo metadata` succeeds in the current directory,
+/// crates are ordered by the dependency graph,
+/// the outputs of each application are also written to `<out_dir>/yew-style-in-rs/<app>/`
+/// and the [`Config`] of the workspace is applied.
+/// `YEW_STYLE_IN_RS_OUT_DIR` or `out-dir` of the configuration changes the directory of the outputs.
+/// Returns the paths of the written files.
+pub fn collect(profile_dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+    let profile_dir = profile_dir.as_ref();
+    let mut collector = Collector::new(profile_dir);
+    if let Ok(graph) = cargo_build_graph(env::current_dir()?) {
+        collector = collector.crate_order(graph.packages).apps(graph.apps);
+    }
+    let workspace_root = cargo_workspace(env::current_dir()?).map(|workspace| workspace.root);
+    if let Some(root) = config_root(workspace_root.ok()) {
+        let config = Config::load(&root)?;
+        collector = collector
+            .out_dir(out_dir(profile_dir, &root, &config))
+            .config(config);
+    }
+    collector.collect()
+}
+
+/// Get the directory of the configuration.
+///
+/// `YEW_STYLE_IN_RS_CONFIG_DIR` if set, otherwise the workspace root.
+pub fn config_root(workspace_root: Option<PathBuf>) -> Option<PathBuf> {
+    env::var_os(CONFIG_DIR_ENV)
+        .map(PathBuf::from)
+        .or(workspace_root)
+}
+
+/// Get the directory of the css files and `yew-style-manifest.json`.
+///
+/// `YEW_STYLE_IN_RS_OUT_DIR` or `out-dir` of the configuration relative to the configuration root,
+/// otherwise the profile directory.
+pub fn out_dir(profile_dir: &Path, config_root: &Path, config: &Config) -> PathBuf {
+    match env::var_os(OUT_DIR_ENV) {
+        Some(dir) => config_root.join(dir),
+        None => match &config.out_dir {
+            Some(dir) => config_root.join(dir),
+            None => profile_dir.to_path_buf(),
+        },
+    }
+}
+
+/// Get the profile directory from a directory of the artifacts in it.
+///
+/// The profile directory is the parent of `deps`, `examples` or `build`,
+/// whatever the profile name, the target triple or the target directory is.
+///
+/// eg)
+/// - `target/debug/deps` -> `target/debug`
+/// - `target/wasm32-unknown-unknown/wasm-release/deps` -> `target/wasm32-unknown-unknown/wasm-release`
+/// - `target/release/build/my-app-0123456789abcdef/out` -> `target/release`
+pub fn profile_dir(artifact_dir: &Path) -> Option<PathBuf> {
+    for dir in artifact_dir.ancestors() {
+        let parent = dir.parent()?;
+        let name = dir.file_name()?;
+        if name == "deps" || name == "examples" {
+            return Some(parent.to_path_buf());
+        }
+        if parent.file_name()? == "build" {
+            return parent.parent().map(Path::to_path_buf);
+        }
+    }
+    None
+}
+
+/// Collector of the CSS fragments with options.
+///
+/// ```rust,no_run
+/// use yew_style_in_rs_build::Collector;
+///
+/// Collector::new("target/release")
+///     .packages(vec!["my-app".into(), "my-components".into()])
+///     .collect()
+///     .unwrap();
+/// ```
+pub struct Collector {
+    profile_dir: PathBuf,
+    // directory of the outputs, the profile directory by default
+    out_dir: Option<PathBuf>,
+    // crate names in the order of the rules
+    packages: Option<Vec<String>>,
+    // remove fragments of crates not in `packages`
+    prune: bool,
+    apps: Vec<App>,
+    config: Config,
+}
+impl Collector {
+    /// Collector of the fragments in `<profile_dir>/build-yew-style-in-rs/`.
+    pub fn new(profile_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            profile_dir: profile_dir.into(),
+            out_dir: None,
+            packages: None,
+            prune: false,
+            apps: vec![],
+            config: Config::default(),
+        }
+    }
+
+    /// Write the css files and `yew-style-manifest.json` into the directory
+    /// instead of the profile directory.
+    ///
+    /// The fragments are always read from the profile directory.
+    pub fn out_dir(mut self, out_dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(out_dir.into());
+        self
+    }
+
+    /// Remove fragments of packages not in the list,
+    /// e.g. of dependencies removed from the build.
+    ///
+    /// Rules are written in the order of the list,
+    /// so list dependencies before dependents like [`cargo_packages`].
+    pub fn packages(mut self, packages: Vec<String>) -> Self {
+        self.packages = Some(packages);
+        self.prune = true;
+        self
+    }
+
+    /// Write rules of crates in the order of the list without removing fragments.
+    ///
+    /// List dependencies before dependents like [`cargo_packages`].
+    /// Crates not in the list come last in name order.
+    pub fn crate_order(mut self, packages: Vec<String>) -> Self {
+        self.packages = Some(packages);
+        self
+    }
+
+    /// Also write the outputs of each application to `<out_dir>/yew-style-in-rs/<app>/`
+    /// with only the fragments of the packages of the app.
+    ///
+    /// With several applications in a workspace,
+    /// the css of an application does not include the components of the others.
+    pub fn apps(mut self, apps: Vec<App>) -> Self {
+        self.apps = apps;
+        self
+    }
+
+    /// Apply the layers and the formatting of the configuration to the css files.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Write the css files and `yew-style-manifest.json`.
+    ///
+    /// Atomic rules come first, then rules of crates in the order of [`Collector::packages`]
+    /// or [`Collector::crate_order`], or in name order, then in source file and line order within a crate.
+    /// Css files previously written to the directory are removed.
+    /// Returns the paths of the written files.
+    pub fn collect(&self) -> Result<Vec<PathBuf>> {
+        let out_dir = self.out_dir.as_ref().unwrap_or(&self.profile_dir);
+        let build_path = self.profile_dir.join(FRAGMENT_DIR);
+
+        // Generate build path is not exists
+        if !build_path.exists() {
+            fs::create_dir_all(&build_path)?;
+        }
+
+        // Removing CSS files from a deleted package
+        if let (true, Some(packages)) = (self.prune, &self.packages) {
+            for entry in fs::read_dir(&build_path)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    if let Some(package_name) = path.file_name().and_then(|n| n.to_str()) {
+                        if !packages.iter().any(|p| p == package_name) {
+                            fs::remove_dir_all(path)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Read fragments
+        let mut fragments = vec![];
+        for crate_dir in fs::read_dir(&build_path)? {
+            let crate_dir = crate_dir?.path();
+            if !crate_dir.is_dir() {
+                continue;
+            }
+            let crate_name = crate_dir
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            for entry in fs::read_dir(&crate_dir)? {
+                let path = entry?.path();
+                let id = path.file_name().unwrap().to_string_lossy().into_owned();
+                let fragment = Fragment::parse(&fs::read_to_string(&path)?);
+                fragments.push((crate_name.clone(), id, fragment));
+            }
+        }
+
+        // Sort fragments by crate in dependency order, source file and line.
+        // The id breaks ties, so the order never depends on the file system.
+        let crate_rank = |crate_name: &str| {
+            self.packages
+                .as_ref()
+                .and_then(|packages| packages.iter().position(|p| p == crate_name))
+                .unwrap_or(usize::MAX)
+        };
+        fragments.sort_by(|(crate_a, id_a, a), (crate_b, id_b, b)| {
+            (
+                crate_rank(crate_a),
+                crate_a,
+                &a.meta.file,
+                a.meta.line,
+                id_a,
+            )
+                .cmp(&(
+                    crate_rank(crate_b),
+                    crate_b,
+                    &b.meta.file,
+                    b.meta.line,
+                    id_b,
+                ))
+        });
+
+        // Write outputs of all crates and of each application
+        let mut written = write_outputs(out_dir, fragments.iter(), &self.config)?;
+        for app in &self.apps {
+            let app_fragments = fragments
+                .iter()
+                .filter(|(crate_name, _, _)| app.packages.contains(crate_name));
+            let app_dir = out_dir.join(APP_DIR).join(&app.name);
+            written.append(&mut write_outputs(&app_dir, app_fragments, &self.config)?);
+        }
+
+        Ok(written)
+    }
+}
+
+// Write the css files and `yew-style-manifest.json` of the sorted fragments into the directory.
+// Css files previously written to the directory are removed.
+fn write_outputs<'a>(
+    dir: &Path,
+    fragments: impl Iterator<Item = &'a (String, String, Fragment)>,
+    config: &Config,
+) -> Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Remove css file in build directory
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "css") {
+            fs::remove_file(path)?;
+        }
+    }
+
+    let mut css_map = BTreeMap::new();
+    let mut atomic_rules = BTreeMap::new();
+    let mut manifest_entries = vec![];
+    for (crate_name, id, fragment) in fragments {
+        manifest_entries.push(ManifestEntry {
+            id: id.clone(),
+            crate_name: crate_name.clone(),
+            file: fragment.meta.file.clone(),
+            line: fragment.meta.line,
+            name: fragment.meta.name.clone(),
+            filename: fragment.filename.clone(),
+            keyframes: fragment.meta.keyframes.clone(),
+            bytes: fragment.bytes(),
+        });
+        // atomic rules in the order of the first use
+        let (rules, seen) = atomic_rules
+            .entry(&fragment.filename)
+            .or_insert((vec![], HashSet::new()));
+        for rule in &fragment.atomic_rules {
+            if seen.insert(rule) {
+                rules.push(rule.as_str());
+            }
+        }
+        css_map
+            .entry(&fragment.filename)
+            .or_insert(vec![])
+            .push((config.layer_of(crate_name), fragment.css.as_str()));
+    }
+
+    // Write css files
+    let mut written = vec![];
+    for (filename, contents) in css_map {
+        let (rules, _) = atomic_rules.remove(filename).unwrap_or_default();
+        let css = if config.layers.is_empty() {
+            let contents = contents.into_iter().map(|(_, css)| css);
+            rules.concat() + &contents.collect::<String>()
+        } else {
+            layered_css(config, rules, contents)
+        };
+        let css = if config.minify {
+            format::minify(&css)
+        } else if config.pretty {
+            format::pretty(&css)
+        } else {
+            css
+        };
+        let path = dir.join(format!("{filename}.css"));
+        fs::write(&path, css)?;
+        written.push(path);
+    }
+
+    // Write manifest in the order of the rules
+    let manifest = Manifest {
+        version: 1,
+        styles: manifest_entries,
+    };
+    let path = dir.join("yew-style-manifest.json");
+    fs::write(&path, serde_json::to_string_pretty(&manifest)?)?;
+    written.push(path);
+
+    Ok(written)
+}
+
+// Wrap the rules of the crates in the layers of the configuration.
+//
+// The layer order is declared first, so the order of the rules does not matter.
+// Atomic rules are in the first layer to be overridden by the scoped rules.
+// Rules of crates not in any layer come last and win over every layer.
+//
+// eg)
+// @layer base,app;@layer base{.a-AbCdEfGh{...}.IjKlMnOp{...}}@layer app{...}.QrStUvWx{...}
+fn layered_css(
+    config: &Config,
+    atomic_rules: Vec<&str>,
+    contents: Vec<(Option<&str>, &str)>,
+) -> String {
+    let names = config.layers.iter().map(|l| l.name.as_str());
+    let mut css = format!("@layer {};", names.collect::<Vec<_>>().join(","));
+    for (i, layer) in config.layers.iter().enumerate() {
+        let mut rules = if i == 0 {
+            atomic_rules.concat()
+        } else {
+            String::new()
+        };
+        for (_, content) in contents.iter().filter(|(l, _)| *l == Some(&layer.name)) {
+            rules.push_str(content);
+        }
+        if !rules.is_empty() {
+            css += &format!("@layer {}{{{rules}}}", layer.name);
+        }
+    }
+    for (_, content) in contents.iter().filter(|(l, _)| l.is_none()) {
+        css.push_str(content);
+    }
+    css
+}
+
+// An entry of `yew-style-manifest.json`.
+#[derive(Serialize)]
+struct ManifestEntry {
+    id: String,
+    #[serde(rename = "crate")]
+    crate_name: String,
+    file: String,
+    line: usize,
+    name: String,
+    // output css filename without `.css`
+    filename: String,
+    keyframes: Vec<String>,
+    // byte size of the css of the declaration including its atomic rules
+    bytes: usize,
+}
+
+// `yew-style-manifest.json` written next to `style.css`.
+#[derive(Serialize)]
+struct Manifest {
+    version: u32,
+    styles: Vec<ManifestEntry>,
+}
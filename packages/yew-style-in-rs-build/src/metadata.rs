@@ -0,0 +1,249 @@
+// Packages of the build from `cargo metadata`.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Deserialize)]
+pub(crate) struct Target {
+    pub kind: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Package {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub targets: Vec<Target>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct DepKind {
+    pub kind: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct NodeDep {
+    pub pkg: String,
+    #[serde(default)]
+    pub dep_kinds: Vec<DepKind>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Node {
+    pub id: String,
+    #[serde(default)]
+    pub dependencies: Vec<String>,
+    #[serde(default)]
+    pub deps: Vec<NodeDep>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Resolve {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Metadata {
+    pub packages: Vec<Package>,
+    #[serde(default)]
+    pub workspace_members: Vec<String>,
+    pub resolve: Option<Resolve>,
+    #[serde(default)]
+    pub workspace_root: PathBuf,
+    #[serde(default)]
+    pub target_directory: PathBuf,
+}
+
+/// A final application crate of the workspace, a member with a `bin` or `cdylib` target.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct App {
+    pub name: String,
+    /// Packages in the dependency closure of the app, including itself,
+    /// in dependency order.
+    pub packages: Vec<String>,
+}
+
+/// Packages and applications of the build from `cargo metadata`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuildGraph {
+    /// Package names in dependency order, dependencies before dependents.
+    pub packages: Vec<String>,
+    /// Applications in name order.
+    pub apps: Vec<App>,
+}
+
+/// Directories of the workspace from `cargo metadata`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Workspace {
+    pub root: PathBuf,
+    /// The target directory, respecting `CARGO_TARGET_DIR` and `build.target-dir`.
+    pub target_directory: PathBuf,
+}
+
+/// Get the directories of the workspace of the manifest directory.
+///
+/// Executes `cargo metadata --no-deps` in the directory.
+pub fn cargo_workspace(manifest_dir: impl AsRef<Path>) -> Result<Workspace> {
+    let metadata = cargo_metadata(manifest_dir.as_ref(), &["--no-deps"])?;
+    Ok(Workspace {
+        root: metadata.workspace_root,
+        target_directory: metadata.target_directory,
+    })
+}
+
+/// Get the packages and the applications of the build of the manifest directory.
+///
+/// Executes `cargo metadata` in the directory.
+pub fn cargo_build_graph(manifest_dir: impl AsRef<Path>) -> Result<BuildGraph> {
+    let metadata = cargo_metadata(manifest_dir.as_ref(), &[])?;
+    Ok(build_graph(&metadata))
+}
+
+/// Get the package names of the build of the manifest directory
+/// in dependency order, dependencies before dependents.
+///
+/// Executes `cargo metadata` in the directory.
+pub fn cargo_packages(manifest_dir: impl AsRef<Path>) -> Result<Vec<String>> {
+    Ok(cargo_build_graph(manifest_dir)?.packages)
+}
+
+fn cargo_metadata(manifest_dir: &Path, args: &[&str]) -> Result<Metadata> {
+    let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".into());
+    let output = Command::new(cargo)
+        .arg("metadata")
+        .arg("--format-version=1")
+        .args(args)
+        .current_dir(manifest_dir)
+        .output()
+        .context("Failed to execute cargo metadata")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+pub(crate) fn build_graph(metadata: &Metadata) -> BuildGraph {
+    let graph = Graph::new(metadata);
+
+    let mut ids = metadata
+        .packages
+        .iter()
+        .map(|p| p.id.as_str())
+        .collect::<Vec<_>>();
+    ids.sort_by_key(|id| (graph.names[id], *id));
+    let packages = graph.dependency_order(&ids, false);
+
+    let mut apps = metadata
+        .packages
+        .iter()
+        .filter(|p| metadata.workspace_members.contains(&p.id))
+        .filter(|p| {
+            p.targets
+                .iter()
+                .any(|t| t.kind.iter().any(|kind| kind == "bin" || kind == "cdylib"))
+        })
+        .map(|p| App {
+            name: p.name.clone(),
+            packages: graph.dependency_order(&[p.id.as_str()], true),
+        })
+        .collect::<Vec<_>>();
+    apps.sort_by(|a, b| a.name.cmp(&b.name));
+
+    BuildGraph { packages, apps }
+}
+
+// Dependency graph of package ids.
+struct Graph<'a> {
+    names: HashMap<&'a str, &'a str>,
+    // all dependencies including dev and build dependencies
+    dependencies: HashMap<&'a str, Vec<&'a str>>,
+    // dependencies linked into the package
+    normal_dependencies: HashMap<&'a str, Vec<&'a str>>,
+}
+impl<'a> Graph<'a> {
+    fn new(metadata: &'a Metadata) -> Self {
+        let names = metadata
+            .packages
+            .iter()
+            .map(|p| (p.id.as_str(), p.name.as_str()))
+            .collect::<HashMap<_, _>>();
+        let sort = |mut ids: Vec<&'a str>| {
+            ids.sort_by_key(|id| names.get(id).copied().unwrap_or(id));
+            ids
+        };
+
+        let mut dependencies = HashMap::new();
+        let mut normal_dependencies = HashMap::new();
+        for node in metadata.resolve.iter().flat_map(|resolve| &resolve.nodes) {
+            let all = node.dependencies.iter().map(String::as_str).collect();
+            dependencies.insert(node.id.as_str(), sort(all));
+
+            // `deps` is absent in old cargo, so fall back to all dependencies
+            let normal = if node.deps.is_empty() {
+                node.dependencies.iter().map(String::as_str).collect()
+            } else {
+                node.deps
+                    .iter()
+                    .filter(|dep| {
+                        dep.dep_kinds.is_empty()
+                            || dep.dep_kinds.iter().any(|kind| kind.kind.is_none())
+                    })
+                    .map(|dep| dep.pkg.as_str())
+                    .collect()
+            };
+            normal_dependencies.insert(node.id.as_str(), sort(normal));
+        }
+
+        Self {
+            names,
+            dependencies,
+            normal_dependencies,
+        }
+    }
+
+    // Sort the packages reachable from the roots topologically with dependencies first.
+    // Dependencies are visited in name order, so the order is deterministic.
+    // Cycles through dev-dependencies are broken at the first visited package.
+    fn dependency_order(&self, roots: &[&'a str], normal_only: bool) -> Vec<String> {
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        for root in roots {
+            self.visit(root, normal_only, &mut visited, &mut order);
+        }
+        order
+    }
+
+    fn visit(
+        &self,
+        id: &'a str,
+        normal_only: bool,
+        visited: &mut HashSet<&'a str>,
+        order: &mut Vec<String>,
+    ) {
+        if !visited.insert(id) {
+            return;
+        }
+        let dependencies = if normal_only {
+            &self.normal_dependencies
+        } else {
+            &self.dependencies
+        };
+        if let Some(deps) = dependencies.get(id) {
+            for dep in deps {
+                self.visit(dep, normal_only, visited, order);
+            }
+        }
+        if let Some(name) = self.names.get(id) {
+            if !order.iter().any(|n| n == name) {
+                order.push(name.to_string());
+            }
+        }
+    }
+}
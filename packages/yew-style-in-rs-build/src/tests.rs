@@ -0,0 +1,478 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::*;
+
+// Create an empty profile directory in the temp directory.
+fn profile_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir()
+        .join(format!("yew-style-in-rs-build-{}", std::process::id()))
+        .join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(dir.join(FRAGMENT_DIR)).unwrap();
+    dir
+}
+
+fn write_fragment(dir: &Path, crate_name: &str, id: &str, content: &str) {
+    let crate_dir = dir.join(FRAGMENT_DIR).join(crate_name);
+    fs::create_dir_all(&crate_dir).unwrap();
+    fs::write(crate_dir.join(id), content).unwrap();
+}
+
+#[test]
+fn test_collect_1() {
+    let dir = profile_dir("collect_1");
+    write_fragment(
+        &dir,
+        "app",
+        "AbCdEfGh",
+        "style\n@meta {\"file\":\"src/lib.rs\",\"line\":3,\"name\":\"css\",\"keyframes\":[]}\n.AbCdEfGh:hover{color:red;}",
+    );
+    write_fragment(
+        &dir,
+        "components",
+        "IjKlMnOp",
+        "style\n@atomic .a-Flex{display:flex;}\n.IjKlMnOp>p{color:blue;}",
+    );
+    write_fragment(
+        &dir,
+        "components",
+        "QrStUvWx",
+        "style\n@atomic .a-Flex{display:flex;}\n",
+    );
+    write_fragment(
+        &dir,
+        "components",
+        "YzAbCdEf",
+        "important\n.YzAbCdEf{color:black;}",
+    );
+    fs::write(dir.join("stale.css"), "").unwrap();
+
+    let mut written = Collector::new(&dir).collect().unwrap();
+    written.sort();
+    assert_eq!(
+        written,
+        vec![
+            dir.join("important.css"),
+            dir.join("style.css"),
+            dir.join("yew-style-manifest.json"),
+        ]
+    );
+    assert!(!dir.join("stale.css").exists());
+
+    // atomic rules are deduplicated and written before the scoped rules
+    let css = fs::read_to_string(dir.join("style.css")).unwrap();
+    assert!(css.starts_with(".a-Flex{display:flex;}."));
+    assert_eq!(css.matches(".a-Flex").count(), 1);
+    assert!(css.contains(".AbCdEfGh:hover{color:red;}"));
+    assert!(css.contains(".IjKlMnOp>p{color:blue;}"));
+    assert_eq!(
+        fs::read_to_string(dir.join("important.css")).unwrap(),
+        ".YzAbCdEf{color:black;}"
+    );
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("yew-style-manifest.json")).unwrap())
+            .unwrap();
+    let styles = manifest["styles"].as_array().unwrap();
+    assert_eq!(styles.len(), 4);
+    assert_eq!(styles[0]["id"], "AbCdEfGh");
+    assert_eq!(styles[0]["crate"], "app");
+    assert_eq!(styles[0]["file"], "src/lib.rs");
+    assert_eq!(styles[0]["line"], 3);
+    assert_eq!(styles[0]["bytes"], ".AbCdEfGh:hover{color:red;}".len());
+    assert_eq!(styles[3]["filename"], "important");
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_collect_2() {
+    // fragments of packages not in the list are removed
+    let dir = profile_dir("collect_2");
+    write_fragment(&dir, "app", "AbCdEfGh", "style\n.AbCdEfGh{color:red;}");
+    write_fragment(&dir, "removed", "IjKlMnOp", "style\n.IjKlMnOp{color:blue;}");
+
+    Collector::new(&dir)
+        .packages(vec!["app".into()])
+        .collect()
+        .unwrap();
+    assert!(!dir.join(FRAGMENT_DIR).join("removed").exists());
+    assert_eq!(
+        fs::read_to_string(dir.join("style.css")).unwrap(),
+        ".AbCdEfGh{color:red;}"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_collect_3() {
+    // rules are ordered by crate in the order of packages, source file and line
+    let dir = profile_dir("collect_3");
+    let fragment = |file: &str, line: usize, css: &str| {
+        format!("style\n@meta {{\"file\":\"{file}\",\"line\":{line},\"name\":\"css\"}}\n{css}")
+    };
+    write_fragment(
+        &dir,
+        "app",
+        "Aaaaaaaa",
+        &fragment("src/lib.rs", 20, ".app2{}"),
+    );
+    write_fragment(
+        &dir,
+        "app",
+        "Bbbbbbbb",
+        &fragment("src/lib.rs", 3, ".app1{}"),
+    );
+    write_fragment(&dir, "app", "Cccccccc", &fragment("src/a.rs", 9, ".app0{}"));
+    write_fragment(&dir, "ui", "Dddddddd", &fragment("src/lib.rs", 1, ".ui{}"));
+
+    Collector::new(&dir)
+        .packages(vec!["ui".into(), "app".into()])
+        .collect()
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("style.css")).unwrap(),
+        ".ui{}.app0{}.app1{}.app2{}"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_collect_4() {
+    // outputs of each application contain only the fragments of its packages
+    let dir = profile_dir("collect_4");
+    write_fragment(&dir, "ui", "Aaaaaaaa", "style\n.ui{}");
+    write_fragment(&dir, "admin", "Bbbbbbbb", "style\n.admin{}");
+    write_fragment(&dir, "shop", "Cccccccc", "style\n.shop{}");
+
+    Collector::new(&dir)
+        .crate_order(vec!["ui".into(), "admin".into(), "shop".into()])
+        .apps(vec![
+            App {
+                name: "admin".into(),
+                packages: vec!["ui".into(), "admin".into()],
+            },
+            App {
+                name: "shop".into(),
+                packages: vec!["ui".into(), "shop".into()],
+            },
+        ])
+        .collect()
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("style.css")).unwrap(),
+        ".ui{}.admin{}.shop{}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join(APP_DIR).join("admin").join("style.css")).unwrap(),
+        ".ui{}.admin{}"
+    );
+    assert_eq!(
+        fs::read_to_string(dir.join(APP_DIR).join("shop").join("style.css")).unwrap(),
+        ".ui{}.shop{}"
+    );
+    assert!(dir
+        .join(APP_DIR)
+        .join("shop")
+        .join("yew-style-manifest.json")
+        .exists());
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_build_graph_1() {
+    use super::metadata::*;
+
+    let metadata: Metadata = serde_json::from_str(
+        r#"{
+            "packages": [
+                { "id": "admin 0.1.0", "name": "admin", "targets": [{ "kind": ["bin"] }] },
+                { "id": "shop 0.1.0", "name": "shop", "targets": [{ "kind": ["cdylib", "rlib"] }] },
+                { "id": "ui 0.1.0", "name": "ui", "targets": [{ "kind": ["lib"] }] },
+                { "id": "icons 0.1.0", "name": "icons", "targets": [{ "kind": ["lib"] }] },
+                { "id": "testing 0.1.0", "name": "testing", "targets": [{ "kind": ["lib"] }] },
+                { "id": "yew-style-in-rs 0.4.1", "name": "yew-style-in-rs", "targets": [{ "kind": ["lib"] }] }
+            ],
+            "workspace_members": ["admin 0.1.0", "shop 0.1.0", "ui 0.1.0", "icons 0.1.0", "testing 0.1.0"],
+            "resolve": {
+                "nodes": [
+                    {
+                        "id": "admin 0.1.0",
+                        "dependencies": ["testing 0.1.0", "ui 0.1.0"],
+                        "deps": [
+                            { "pkg": "testing 0.1.0", "dep_kinds": [{ "kind": "dev" }] },
+                            { "pkg": "ui 0.1.0", "dep_kinds": [{ "kind": null }] }
+                        ]
+                    },
+                    {
+                        "id": "shop 0.1.0",
+                        "dependencies": ["icons 0.1.0", "yew-style-in-rs 0.4.1"],
+                        "deps": [
+                            { "pkg": "icons 0.1.0", "dep_kinds": [{ "kind": null }] },
+                            { "pkg": "yew-style-in-rs 0.4.1", "dep_kinds": [{ "kind": null }] }
+                        ]
+                    },
+                    { "id": "ui 0.1.0", "dependencies": ["icons 0.1.0", "yew-style-in-rs 0.4.1"] },
+                    { "id": "icons 0.1.0", "dependencies": ["yew-style-in-rs 0.4.1"] },
+                    { "id": "testing 0.1.0", "dependencies": [] },
+                    { "id": "yew-style-in-rs 0.4.1", "dependencies": [] }
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let graph = build_graph(&metadata);
+
+    assert_eq!(
+        graph.packages,
+        vec!["testing", "yew-style-in-rs", "icons", "ui", "admin", "shop"]
+    );
+    assert_eq!(
+        graph.apps,
+        vec![
+            App {
+                name: "admin".into(),
+                packages: vec![
+                    "yew-style-in-rs".into(),
+                    "icons".into(),
+                    "ui".into(),
+                    "admin".into()
+                ],
+            },
+            App {
+                name: "shop".into(),
+                packages: vec!["yew-style-in-rs".into(), "icons".into(), "shop".into()],
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_profile_dir_1() {
+    let cases = [
+        ("target/debug/deps", Some("target/debug")),
+        ("target/release/examples", Some("target/release")),
+        (
+            "/work/target/wasm-release/deps",
+            Some("/work/target/wasm-release"),
+        ),
+        (
+            "target/wasm32-unknown-unknown/release/deps",
+            Some("target/wasm32-unknown-unknown/release"),
+        ),
+        (
+            "/tmp/build/target/debug/build/app-0123456789abcdef/out",
+            Some("/tmp/build/target/debug"),
+        ),
+        ("/tmp/out", None),
+    ];
+    for (artifact_dir, expected) in cases {
+        assert_eq!(
+            super::profile_dir(Path::new(artifact_dir)),
+            expected.map(PathBuf::from),
+            "{artifact_dir}"
+        );
+    }
+}
+
+#[test]
+fn test_config_1() {
+    let config = Config::parse(
+        r#"
+        out-dir = "dist/css"
+        default-filename = "app"
+        class-prefix = "ys-"
+        pretty = true
+
+        [id]
+        scheme = "random"
+        length = 10
+
+        [targets]
+        safari = "15.4"
+        firefox = 78
+
+        [[layers]]
+        name = "components"
+        crates = ["my-components"]
+
+        [[layers]]
+        name = "app"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(config.out_dir, Some(PathBuf::from("dist/css")));
+    assert_eq!(config.default_filename, "app");
+    assert_eq!(config.class_prefix, "ys-");
+    assert!(config.pretty);
+    assert!(!config.minify);
+    assert_eq!(
+        config.id,
+        IdConfig {
+            scheme: IdScheme::Random,
+            length: 10
+        }
+    );
+    assert_eq!(config.targets[&Browser::Safari], Version::new(15, 4));
+    assert_eq!(config.targets[&Browser::Firefox], Version::new(78, 0));
+    assert_eq!(config.layer_of("my-components"), Some("components"));
+    assert_eq!(config.layer_of("my-app"), None);
+
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+}
+
+#[test]
+fn test_config_2() {
+    // unknown keys and invalid values are errors
+    let error = |content: &str| format!("{:#}", Config::parse(content).unwrap_err());
+
+    assert!(error("minfy = true").contains("unknown field `minfy`"));
+    assert!(error("[id]\nsize = 8").contains("unknown field `size`"));
+    assert!(error("[targets]\nnetscape = \"4\"").contains("unknown variant `netscape`"));
+    assert!(error("class-prefix = \"1a\"").contains("class-prefix"));
+    assert!(error("default-filename = \"style.css\"").contains("default-filename"));
+    assert!(error("[id]\nlength = 2").contains("id.length"));
+    assert!(error("minify = true\npretty = true").contains("minify"));
+    assert!(error("[[layers]]\nname = \"a\"\n[[layers]]\nname = \"a\"").contains("twice"));
+}
+
+#[test]
+fn test_config_3() {
+    // configuration in the metadata of Cargo.toml
+    let dir = profile_dir("config_3");
+    fs::write(
+        dir.join("Cargo.toml"),
+        r#"
+        [workspace]
+        members = ["app"]
+
+        [workspace.metadata.yew-style-in-rs]
+        default-filename = "app"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(Config::load(&dir).unwrap().default_filename, "app");
+    let (_, path) = Config::load_with_path(&dir).unwrap();
+    assert_eq!(path, Some(dir.join("Cargo.toml")));
+
+    // Cargo.toml without the metadata is not the configuration
+    fs::write(dir.join("Cargo.toml"), "[workspace]\nmembers = [\"app\"]").unwrap();
+    assert_eq!(
+        Config::load_with_path(&dir).unwrap(),
+        (Config::default(), None)
+    );
+
+    fs::write(dir.join(CONFIG_FILE), "unknown = 1").unwrap();
+    let error = format!("{:#}", Config::load(&dir).unwrap_err());
+    assert!(error.contains(CONFIG_FILE));
+    assert!(error.contains("unknown field `unknown`"));
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_format_1() {
+    let css = r#".AbCdEfGh{color:red;content:"a;b}";}@media (min-width:480px){.AbCdEfGh:hover{opacity:0.5;}}"#;
+    assert_eq!(
+        format::minify(css),
+        r#".AbCdEfGh{color:red;content:"a;b}"}@media (min-width:480px){.AbCdEfGh:hover{opacity:0.5}}"#
+    );
+    assert_eq!(
+        format::pretty(css),
+        r#".AbCdEfGh {
+  color: red;
+  content: "a;b}";
+}
+@media (min-width:480px) {
+  .AbCdEfGh:hover {
+    opacity: 0.5;
+  }
+}
+"#
+    );
+}
+
+#[test]
+fn test_collect_5() {
+    // rules of crates are wrapped in the layers of the configuration
+    let dir = profile_dir("collect_5");
+    write_fragment(
+        &dir,
+        "app",
+        "Aaaaaaaa",
+        "style\n@atomic .a-Xxxxxxxx{color:red;}\n.app{}",
+    );
+    write_fragment(&dir, "ui", "Bbbbbbbb", "style\n.ui{}");
+    write_fragment(&dir, "reset", "Cccccccc", "style\n.reset{}");
+
+    let config = Config::parse(
+        r#"
+        [[layers]]
+        name = "reset"
+        crates = ["reset"]
+
+        [[layers]]
+        name = "components"
+        crates = ["ui"]
+        "#,
+    )
+    .unwrap();
+    Collector::new(&dir)
+        .crate_order(vec!["reset".into(), "ui".into(), "app".into()])
+        .config(config)
+        .collect()
+        .unwrap();
+    assert_eq!(
+        fs::read_to_string(dir.join("style.css")).unwrap(),
+        "@layer reset,components;@layer reset{.a-Xxxxxxxx{color:red;}.reset{}}@layer components{.ui{}}.app{}"
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_collect_6() {
+    // outputs are written to the output directory, fragments stay in the profile directory
+    let dir = profile_dir("collect_6");
+    write_fragment(&dir, "app", "Aaaaaaaa", "style\n.app{}");
+
+    let out_dir = dir.join("dist");
+    let written = Collector::new(&dir)
+        .out_dir(&out_dir)
+        .apps(vec![App {
+            name: "app".into(),
+            packages: vec!["app".into()],
+        }])
+        .collect()
+        .unwrap();
+    assert!(written.contains(&out_dir.join("style.css")));
+    assert!(written.contains(&out_dir.join("yew-style-in-rs/app/style.css")));
+    assert_eq!(
+        fs::read_to_string(out_dir.join("style.css")).unwrap(),
+        ".app{}"
+    );
+    assert!(!dir.join("style.css").exists());
+    assert!(!out_dir.join(FRAGMENT_DIR).exists());
+
+    let config = Config {
+        out_dir: Some("dist/css".into()),
+        ..Config::default()
+    };
+    assert_eq!(
+        super::out_dir(&dir, Path::new("/root"), &config),
+        Path::new("/root/dist/css")
+    );
+    assert_eq!(
+        super::out_dir(&dir, Path::new("/root"), &Config::default()),
+        dir
+    );
+
+    fs::remove_dir_all(dir).unwrap();
+}
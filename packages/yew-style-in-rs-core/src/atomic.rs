@@ -0,0 +1,82 @@
+// Atomic CSS.
+//
+// Top level properties of a `css!` declaration are split into classes
+// with only one declaration each, e.g. `display: flex;` -> `.a-AbCdEfGh{display:flex;}`.
+// The class names are hashed from the declaration,
+// so the same declaration shares one class across every crate in the build.
+//
+// Nested selectors and at-rules can not be shared,
+// so they are left in the scoped class.
+
+use crate::ast::*;
+use crate::id::hashed_id;
+
+// Prefix of atomic class names to avoid collisions with scoped ids.
+pub const ATOMIC_CLASS_PREFIX: &str = "a-";
+
+// Split top level properties into atomic properties.
+//
+// Returns the atomic properties and the rest of the code.
+// Properties declared twice like `display: -webkit-box; display: flex;` are fallbacks,
+// so every declaration of them stays in the scoped class in order.
+// Shorthands and their longhands like `margin` and `margin-top` stay in the scoped class,
+// because the order of atomic rules across crates can not keep the cascade between them.
+pub fn split_atomic(runtime_css: RuntimeCss) -> (Vec<Property>, RuntimeCss) {
+    let mut properties: Vec<Property> = vec![];
+    let mut rest = vec![];
+    for declaration in runtime_css.0 {
+        match declaration {
+            Declaration::Property(property) => properties.push(property),
+            declaration => rest.push(declaration),
+        }
+    }
+
+    let is_related = |a: &str, b: &str| {
+        a != b
+            && (b.strip_prefix(a).is_some_and(|s| s.starts_with('-'))
+                || a.strip_prefix(b).is_some_and(|s| s.starts_with('-')))
+    };
+    let (scoped, atomic): (Vec<_>, Vec<_>) = properties.iter().cloned().partition(|property| {
+        let declared = properties
+            .iter()
+            .filter(|p| p.property == property.property)
+            .count();
+        declared > 1
+            || properties
+                .iter()
+                .any(|p| is_related(&p.property, &property.property))
+    });
+
+    let mut declarations = scoped
+        .into_iter()
+        .map(Declaration::Property)
+        .collect::<Vec<_>>();
+    declarations.append(&mut rest);
+    (atomic, RuntimeCss(declarations))
+}
+
+// Class name of the atomic property.
+// `prefix` is the class prefix of the configuration put before `a-`.
+//
+// eg)
+// display: flex -> a-AbCdEfGh
+pub fn atomic_class(property: &Property, prefix: &str) -> String {
+    let declaration = format!("{}:{}", property.property, property.value);
+    format!(
+        "{prefix}{ATOMIC_CLASS_PREFIX}{}",
+        hashed_id(&declaration, 0, 8)
+    )
+}
+
+// Rule of the atomic property.
+//
+// eg)
+// display: flex -> .a-AbCdEfGh{display:flex;}
+pub fn atomic_rule(property: &Property, prefix: &str) -> String {
+    format!(
+        ".{}{{{}:{};}}",
+        atomic_class(property, prefix),
+        property.property,
+        property.value
+    )
+}
@@ -0,0 +1,45 @@
+// Stable content hashed ids.
+//
+// The same content always produces the same id across runs, platforms and compilers,
+// so the ids can be shared between server and client or used in snapshot tests.
+// `std::collections::hash_map::DefaultHasher` is not guaranteed to be stable
+// between Rust releases, so FNV-1a 64bit is used instead.
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// FNV-1a 64bit hash.
+pub fn hash(content: &str) -> u64 {
+    content.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    })
+}
+
+// Generate alphabetic id of `len` characters from the hash of content.
+//
+// `salt` is used to resolve collisions.
+// Salt 0 is the plain hash of content, and others hash content with the salt appended.
+//
+// eg)
+// hashed_id("background: black;", 0, 8) -> "fObqASSV"
+pub fn hashed_id(content: &str, salt: u32, len: usize) -> String {
+    let mut hash = if salt == 0 {
+        self::hash(content)
+    } else {
+        self::hash(&format!("{content}\0{salt}"))
+    };
+
+    let base = ALPHABET.len() as u64;
+    let mut id = String::with_capacity(len);
+    for _ in 0..len {
+        id.push(ALPHABET[(hash % base) as usize] as char);
+        hash /= base;
+        // Refill the hash for long ids.
+        if hash == 0 {
+            hash = self::hash(&id);
+        }
+    }
+    id
+}
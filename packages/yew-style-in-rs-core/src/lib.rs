@@ -1,7 +1,9 @@
 //! CSS ast and transpiler for CSS Nesting
 
 pub mod ast;
+pub mod atomic;
 mod cursor;
+pub mod id;
 pub mod transpiler;
 
 #[cfg(test)]
@@ -1148,6 +1148,43 @@ fn test_to_style_string_6() {
     assert_eq!(transpiled_style, expected_style);
 }
 
+#[test]
+fn test_to_rule_strings_1() {
+    // .foo { color: blue; }
+    // @media (orientation: landscape) {
+    //   .foo { color: red; }
+    // }
+    let transpiled_css = TranspiledCss(vec![
+        Rule::QualifiedRule(QualifiedRule {
+            selectors: Selectors(vec![".foo".into()]),
+            block: vec![Declaration::Property(Property {
+                property: "color".into(),
+                value: "blue".into(),
+            })],
+        }),
+        Rule::AtRule(AtRule {
+            rule_name: "media".into(),
+            rule_value: "(orientation: landscape)".into(),
+            block: Some(vec![Declaration::QualifiedRule(QualifiedRule {
+                selectors: Selectors(vec![".foo".into()]),
+                block: vec![Declaration::Property(Property {
+                    property: "color".into(),
+                    value: "red".into(),
+                })],
+            })]),
+        }),
+    ]);
+
+    let rule_strings = transpiled_css.to_rule_strings();
+
+    let expected_rules = vec![
+        ".foo{color:blue;}".to_string(),
+        "@media (orientation: landscape){.foo{color:red;}}".to_string(),
+    ];
+
+    assert_eq!(rule_strings, expected_rules);
+}
+
 #[test]
 fn test_to_style_string_7() {
     // :is(.error, #404):hover > .baz { color: red; }
@@ -1429,3 +1466,116 @@ fn test_to_style_string_15() {
 
     assert_eq!(transpiled_style, expected_style);
 }
+
+#[test]
+fn test_hashed_id_1() {
+    use super::id::*;
+
+    // same content produces same id
+    let id = hashed_id("background: black;", 0, 8);
+    assert_eq!(id, hashed_id("background: black;", 0, 8));
+    assert_eq!(id.len(), 8);
+    assert!(id.chars().all(|c| c.is_ascii_alphabetic()));
+
+    // different content or salt produces different id
+    assert_ne!(id, hashed_id("background: white;", 0, 8));
+    assert_ne!(id, hashed_id("background: black;", 1, 8));
+}
+
+#[test]
+fn test_hashed_id_2() {
+    use super::id::*;
+
+    // FNV-1a 64bit test vectors
+    assert_eq!(hash(""), 0xcbf29ce484222325);
+    assert_eq!(hash("a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(hash("foobar"), 0x85944171f73967e8);
+
+    // long ids are also alphabetic
+    let id = hashed_id("color: red;", 0, 32);
+    assert_eq!(id.len(), 32);
+    assert!(id.chars().all(|c| c.is_ascii_alphabetic()));
+}
+
+#[test]
+fn test_atomic_1() {
+    use super::atomic::*;
+
+    let runtime_css = RuntimeCss::parse(
+        r#"
+            display: flex;
+            color: red;
+            color: green;
+            &:hover {
+                color: blue;
+            }
+        "#,
+    )
+    .expect("Parse Error!");
+    let (atomic, rest) = split_atomic(runtime_css);
+
+    assert_eq!(
+        atomic
+            .iter()
+            .map(|p| atomic_rule(p, ""))
+            .collect::<Vec<_>>(),
+        vec![format!(
+            ".{}{{display:flex;}}",
+            atomic_class(&atomic[0], "")
+        )]
+    );
+    assert!(atomic_class(&atomic[0], "").starts_with(ATOMIC_CLASS_PREFIX));
+    assert_eq!(rest.0.len(), 3);
+    assert!(matches!(rest.0[2], Declaration::QualifiedRule(_)));
+}
+
+#[test]
+fn test_atomic_2() {
+    use super::atomic::*;
+
+    // shorthands and their longhands stay in the scoped class
+    let runtime_css = RuntimeCss::parse(
+        r#"
+            margin: 0;
+            margin-top: 4px;
+            align-items: center;
+        "#,
+    )
+    .expect("Parse Error!");
+    let (atomic, rest) = split_atomic(runtime_css);
+
+    assert_eq!(atomic.len(), 1);
+    assert_eq!(atomic[0].property, "align-items");
+    assert_eq!(rest.0.len(), 2);
+
+    // the same declaration has the same class
+    let a = Property {
+        property: "display".into(),
+        value: "flex".into(),
+    };
+    assert_eq!(atomic_class(&a, ""), atomic_class(&a.clone(), ""));
+}
+
+#[test]
+fn test_atomic_3() {
+    use super::atomic::*;
+
+    // fallbacks of the same property stay in the scoped class in order
+    let runtime_css = RuntimeCss::parse(
+        r#"
+            display: -webkit-box;
+            color: red;
+            display: flex;
+        "#,
+    )
+    .expect("Parse Error!");
+    let (atomic, rest) = split_atomic(runtime_css);
+
+    assert_eq!(atomic.len(), 1);
+    assert_eq!(atomic[0].property, "color");
+    let css = TranspiledCss::transpile(&[".AbCdEfGh".to_string()], rest);
+    assert_eq!(
+        css.to_style_string(),
+        ".AbCdEfGh{display:-webkit-box;display:flex;}"
+    );
+}
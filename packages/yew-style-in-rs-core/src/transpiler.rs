@@ -337,22 +337,20 @@ impl TranspiledCss {
     }
 
     pub fn to_style_string(self) -> String {
-        let mut style_string = String::new();
+        self.to_rule_strings().join("")
+    }
 
-        for rule in self.0 {
-            match rule {
-                Rule::AtRule(at_rule) => {
-                    style_string
-                        .push_str(&declaration_to_style_string(Declaration::AtRule(at_rule)));
-                }
+    // Stringify each top level rule separately.
+    // Used to insert rules into CSSOM one by one with `insertRule`.
+    pub fn to_rule_strings(self) -> Vec<String> {
+        self.0
+            .into_iter()
+            .map(|rule| match rule {
+                Rule::AtRule(at_rule) => declaration_to_style_string(Declaration::AtRule(at_rule)),
                 Rule::QualifiedRule(rule) => {
-                    style_string.push_str(&declaration_to_style_string(
-                        Declaration::QualifiedRule(rule),
-                    ));
+                    declaration_to_style_string(Declaration::QualifiedRule(rule))
                 }
-            }
-        }
-
-        style_string
+            })
+            .collect()
     }
 }
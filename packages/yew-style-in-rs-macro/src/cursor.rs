@@ -21,6 +21,14 @@ impl<'a> Cursor<'a> {
         self.content.peek() == Some(&ch)
     }
 
+    // Check the next char satisfies the predicate without consuming it.
+    pub fn peek_with(&mut self, predicate: impl Fn(char) -> bool) -> bool {
+        self.content
+            .peek()
+            .map(|&c| predicate(c))
+            .unwrap_or_default()
+    }
+
     pub fn next(&mut self) -> Option<char> {
         self.content.next()
     }
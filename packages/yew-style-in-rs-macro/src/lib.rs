@@ -1,10 +1,15 @@
 use proc_macro::TokenStream;
 
 mod cursor;
+mod prefix;
 mod state;
 mod style;
+mod styled;
 mod util;
 
+#[cfg(test)]
+mod tests;
+
 // expand macro with writing css files
 #[proc_macro]
 pub fn style_with_write(tokens: TokenStream) -> TokenStream {
@@ -34,3 +39,17 @@ pub fn style_without_write(tokens: TokenStream) -> TokenStream {
     let style = syn::parse_macro_input!(tokens as style::Style);
     style.expand().into()
 }
+
+// expand `dyn_style!` to `DynStyle` for struct components
+#[proc_macro]
+pub fn dyn_style(tokens: TokenStream) -> TokenStream {
+    let dyn_style = syn::parse_macro_input!(tokens as style::DynStyle);
+    dyn_style.expand().into()
+}
+
+// expand `styled!` to a function component applying the style to an element
+#[proc_macro]
+pub fn styled(tokens: TokenStream) -> TokenStream {
+    let styled = syn::parse_macro_input!(tokens as styled::Styled);
+    styled.expand().into()
+}
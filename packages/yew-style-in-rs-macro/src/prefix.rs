@@ -0,0 +1,148 @@
+// Vendor prefixes for the browser targets of the configuration.
+//
+// Only properties which are still commonly prefixed are listed.
+// A prefixed property is added before the standard property
+// if a target browser is older than the version supporting the standard property.
+
+use std::collections::BTreeMap;
+use yew_style_in_rs_build::{Browser, Version};
+use yew_style_in_rs_core::ast::*;
+
+// (property, prefix, browser, first version supporting the standard property)
+// `None` is not supported yet in any version.
+const PREFIXES: &[(&str, &str, Browser, Option<Version>)] = &[
+    (
+        "appearance",
+        "-webkit-",
+        Browser::Chrome,
+        Some(Version::new(84, 0)),
+    ),
+    (
+        "appearance",
+        "-webkit-",
+        Browser::Edge,
+        Some(Version::new(84, 0)),
+    ),
+    (
+        "appearance",
+        "-webkit-",
+        Browser::Safari,
+        Some(Version::new(15, 4)),
+    ),
+    (
+        "appearance",
+        "-moz-",
+        Browser::Firefox,
+        Some(Version::new(80, 0)),
+    ),
+    (
+        "backdrop-filter",
+        "-webkit-",
+        Browser::Safari,
+        Some(Version::new(18, 0)),
+    ),
+    (
+        "hyphens",
+        "-webkit-",
+        Browser::Safari,
+        Some(Version::new(17, 0)),
+    ),
+    (
+        "mask-image",
+        "-webkit-",
+        Browser::Chrome,
+        Some(Version::new(120, 0)),
+    ),
+    (
+        "mask-image",
+        "-webkit-",
+        Browser::Edge,
+        Some(Version::new(120, 0)),
+    ),
+    (
+        "mask-image",
+        "-webkit-",
+        Browser::Safari,
+        Some(Version::new(15, 4)),
+    ),
+    ("text-size-adjust", "-webkit-", Browser::Safari, None),
+    (
+        "user-select",
+        "-webkit-",
+        Browser::Chrome,
+        Some(Version::new(54, 0)),
+    ),
+    (
+        "user-select",
+        "-webkit-",
+        Browser::Edge,
+        Some(Version::new(79, 0)),
+    ),
+    ("user-select", "-webkit-", Browser::Safari, None),
+    (
+        "user-select",
+        "-moz-",
+        Browser::Firefox,
+        Some(Version::new(69, 0)),
+    ),
+];
+
+// Add vendor prefixed properties needed by the targets.
+//
+// eg) targets: safari 14
+// user-select: none; -> -webkit-user-select: none; user-select: none;
+pub fn add_vendor_prefixes(
+    runtime_css: RuntimeCss,
+    targets: &BTreeMap<Browser, Version>,
+) -> RuntimeCss {
+    if targets.is_empty() {
+        return runtime_css;
+    }
+    RuntimeCss(add_to_block(runtime_css.0, targets))
+}
+
+fn add_to_block(block: Vec<Declaration>, targets: &BTreeMap<Browser, Version>) -> Vec<Declaration> {
+    let mut declarations = vec![];
+    for declaration in block {
+        match declaration {
+            Declaration::Property(property) => {
+                for prefix in prefixes(&property.property, targets) {
+                    declarations.push(Declaration::Property(Property {
+                        property: format!("{prefix}{}", property.property),
+                        value: property.value.clone(),
+                    }));
+                }
+                declarations.push(Declaration::Property(property));
+            }
+            Declaration::QualifiedRule(rule) => {
+                declarations.push(Declaration::QualifiedRule(QualifiedRule {
+                    selectors: rule.selectors,
+                    block: add_to_block(rule.block, targets),
+                }));
+            }
+            Declaration::AtRule(at_rule) => {
+                declarations.push(Declaration::AtRule(AtRule {
+                    block: at_rule.block.map(|block| add_to_block(block, targets)),
+                    ..at_rule
+                }));
+            }
+        }
+    }
+    declarations
+}
+
+// Prefixes of the property needed by the targets without duplicates.
+fn prefixes(property: &str, targets: &BTreeMap<Browser, Version>) -> Vec<&'static str> {
+    let mut prefixes = vec![];
+    for (name, prefix, browser, supported) in PREFIXES {
+        let needed = match (targets.get(browser), supported) {
+            (Some(_), None) => true,
+            (Some(target), Some(supported)) => target < supported,
+            (None, _) => false,
+        };
+        if *name == property && needed && !prefixes.contains(prefix) {
+            prefixes.push(*prefix);
+        }
+    }
+    prefixes
+}
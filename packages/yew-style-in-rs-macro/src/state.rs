@@ -13,8 +13,9 @@
 //
 // The process of writing out the CSS fragments to a file and then combining them
 // into a single `style.css` must be performed after the CSS fragments have been written out.
-// By using the process that is performed when the `STATE` singleton is destroyed in proc macro,
-// we can ensure that it is also executed at the end.
+// `yew_style_in_rs_build::collect` does it once after the build.
+// With the legacy `atexit` feature, it is also performed when the `STATE` singleton
+// is destroyed in proc macro, at the end of every proc macro process.
 // In Rust, Drop is not called on destruction of static objects,
 // so we explicitly specify atexit in libc.
 //
@@ -25,12 +26,30 @@
 use anyhow::Result;
 use fslock::LockFile;
 use once_cell::sync::Lazy;
-use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::io::Write;
 use std::iter::repeat_with;
 use std::sync::Mutex;
+#[cfg(feature = "atexit")]
+use yew_style_in_rs_build::{cargo_build_graph, out_dir, Collector};
+use yew_style_in_rs_build::{Config, FragmentMeta, IdScheme, FRAGMENT_DIR};
+use yew_style_in_rs_core::id::hashed_id;
+
+// Number of salts to try for hashed ids before falling back to random ids.
+const MAX_SALT: u32 = 16;
+
+// Source information of a CSS fragment of the declaration at the span.
+pub fn fragment_meta(span: proc_macro2::Span, name: &str, keyframes: Vec<String>) -> FragmentMeta {
+    FragmentMeta {
+        file: crate::util::source_file(span)
+            .to_string_lossy()
+            .replace('\\', "/"),
+        // 0 if the compiler does not tell the line of spans to proc macros, before Rust 1.88.
+        line: span.start().line,
+        name: name.to_string(),
+        keyframes,
+    }
+}
 
 // It is a singleton inherent in the proc macro process.
 // The timing of when this STATE is destroyed is monitored and
@@ -53,11 +72,14 @@ impl State {
     fn new() -> Result<Self> {
         // Rust does not execute drop traits for static elements,
         // so we explicitly register a libc atexit.
-        extern "C" fn dropper() {
-            let mut state = STATE.lock().unwrap();
-            state.generate_css();
+        #[cfg(feature = "atexit")]
+        {
+            extern "C" fn dropper() {
+                let mut state = STATE.lock().unwrap();
+                state.generate_css();
+            }
+            unsafe { ::libc::atexit(dropper) };
         }
-        unsafe { ::libc::atexit(dropper) };
 
         Ok(Self {
             // build_path,
@@ -78,8 +100,7 @@ impl State {
     pub fn set_write_flag(&mut self, flag: bool) {
         // when first time set write flag to true, delete package temporary files.
         if !self.write_flag() && flag {
-            let out_dir = crate::util::get_out_dir();
-            let build_path = out_dir.join("build-yew-style-in-rs");
+            let build_path = crate::util::get_profile_dir().join(FRAGMENT_DIR);
             let package_path = build_path.join(env::var("CARGO_PKG_NAME").unwrap());
 
             if package_path.exists() {
@@ -96,11 +117,10 @@ impl State {
         self.write_flag = Some(flag);
     }
 
-    // Check `target/release/build-yew-style-in-rs/<CRATE NAME>/<RANDOM 8 CHARACTER>`
+    // Check `target/release/build-yew-style-in-rs/<CRATE NAME>/<8 CHARACTER ID>`
     // is exists or not for every exist <CRATE NAME> directories,
     fn exists_id(&self, id: &str) -> bool {
-        let out_dir = crate::util::get_out_dir();
-        let build_path = out_dir.join("build-yew-style-in-rs");
+        let build_path = crate::util::get_profile_dir().join(FRAGMENT_DIR);
 
         if !build_path.exists() {
             fs::create_dir_all(&build_path).unwrap();
@@ -120,18 +140,30 @@ impl State {
                     .into_iter()
                     .map(|entry| {
                         let entry = entry.unwrap();
-                        let p = entry.path();
-                        p.to_str().unwrap().to_owned()
+                        entry.file_name().to_str().unwrap().to_owned()
                     })
             })
-            .any(|path| path == id)
+            .any(|file_name| file_name == id)
     }
 
-    // Create `target/release/build-yew-style-in-rs/<CRATE NAME>/<RANDOM 8 CHARACTER>`
-    // for new <RANDOM 8 CHARACTER>.
-    pub fn create_random_id_file(&mut self) -> Result<(String, fs::File)> {
-        let out_dir = crate::util::get_out_dir();
-        let build_path = out_dir.join("build-yew-style-in-rs");
+    // Create `target/release/build-yew-style-in-rs/<CRATE NAME>/<8 CHARACTER ID>`
+    // for new <8 CHARACTER ID>.
+    //
+    // The id is hashed from the crate name, the module path, the declaration name and the content,
+    // so that the same source always produces the same `style.css` and class names.
+    // If the id is already used by another fragment, the content is hashed with a salt.
+    // If no free id is found, fall back to a random id.
+    //
+    // The length, the scheme and the prefix of the id follow the configuration.
+    // In debug builds, the id has the readable name of the declaration like `my_crate__css__AbCdEfGh`.
+    pub fn create_id_file(
+        &mut self,
+        config: &Config,
+        module_path: &str,
+        name: &str,
+        content: &str,
+    ) -> Result<(String, fs::File)> {
+        let build_path = crate::util::get_profile_dir().join(FRAGMENT_DIR);
         let package_path = build_path.join(env::var("CARGO_PKG_NAME").unwrap());
         let lockfile_path = build_path.join("lockfile");
 
@@ -146,10 +178,28 @@ impl State {
             fs::create_dir_all(&package_path)?;
         }
 
+        let crate_name = env::var("CARGO_PKG_NAME").unwrap();
+        let seed = format!("{crate_name}\0{module_path}\0{name}\0{content}");
+        let length = config.id.length;
+        let salts = match config.id.scheme {
+            IdScheme::Hash => 0..MAX_SALT,
+            IdScheme::Random => 0..0,
+        };
+        let hashed_ids = salts.map(|salt| hashed_id(&seed, salt, length));
+        let random_ids = repeat_with(|| {
+            repeat_with(fastrand::alphabetic)
+                .take(length)
+                .collect::<String>()
+        });
+
+        let mut ids = hashed_ids.chain(random_ids);
+        let debug_name = if crate::util::debug_assertions() {
+            crate::util::debug_name(name) + "__"
+        } else {
+            String::new()
+        };
         let (id, file) = loop {
-            let id = repeat_with(fastrand::alphabetic)
-                .take(8)
-                .collect::<String>();
+            let id = format!("{}{debug_name}{}", config.class_prefix, ids.next().unwrap());
             let id_path = package_path.join(&id);
             if !self.exists_id(&id) {
                 let file = fs::File::create(id_path)?;
@@ -163,92 +213,29 @@ impl State {
     }
 
     // Remove CSS fragments related to deleted crate,
-    // and remove output style.css and other css,
-    // and write CSS fragments into files.
-    // CSS fragments first line is filename for output css file.
+    // and write CSS fragments into css files and `yew-style-manifest.json`.
+    //
+    // This is the legacy path run at the end of every proc macro process.
+    // `yew_style_in_rs_build::collect` runs it once after the build instead.
+    #[cfg(feature = "atexit")]
     fn generate_css(&mut self) {
         // if not write_flag, do nothing.
         if !self.write_flag() {
             return;
         }
 
-        let out_dir = crate::util::get_out_dir();
-        let build_path = out_dir.join("build-yew-style-in-rs");
-
-        // Generate build path is not exists
-        if !build_path.exists() {
-            fs::create_dir_all(&build_path).unwrap();
-        }
-
-        // Removing CSS files from a deleted package
-        let packages = crate::util::get_cargo_packages();
-        for entry in fs::read_dir(&build_path).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            if path.is_dir() {
-                if let Some(package_name) = path.file_name() {
-                    if let Some(package_name) = package_name.to_str() {
-                        if !packages.contains(&package_name.to_owned()) {
-                            fs::remove_dir_all(path).unwrap();
-                        }
-                    }
-                }
-            }
-        }
-
-        // Remove css file in build directory
-        let out_dir = crate::util::get_out_dir();
-        for entry in fs::read_dir(&out_dir).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "css" {
-                        fs::remove_file(path).unwrap();
-                    }
-                }
-            }
-        }
-
-        // Write css files
-        let mut hashmap = HashMap::new();
-        for p in fs::read_dir(&build_path)
-            .expect("build yew-style-in-rs dir is not exists")
-            .into_iter()
-            .map(|entry| {
-                let entry = entry.unwrap();
-                entry.path()
-            })
-            .filter(|p| p.is_dir())
-            .flat_map(|p| {
-                fs::read_dir(p)
-                    .expect(&format!("some internal dir is not exists"))
-                    .into_iter()
-                    .map(|entry| {
-                        let entry = entry.unwrap();
-                        entry.path()
-                    })
-            })
-        {
-            let content = fs::read_to_string(p).unwrap();
-            let filename = content.lines().next().unwrap().to_string();
-            let content = content
-                .lines()
-                .skip(1)
-                .fold("".to_string(), |mut content, item| {
-                    content.push_str(item);
-                    content
-                });
-            let entry = hashmap.entry(filename).or_insert(vec![]);
-            entry.push(content)
-        }
-        for (filename, contents) in hashmap {
-            let css = contents
-                .into_iter()
-                .reduce(|content, item| content + &item)
-                .unwrap();
-            let mut file = fs::File::create(out_dir.join(format!("{filename}.css"))).unwrap();
-            file.write(css.as_bytes()).unwrap();
-        }
+        let profile_dir = crate::util::get_profile_dir();
+        let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+        let graph = cargo_build_graph(manifest_dir).expect("Failed to get packages of the build");
+        // errors of the configuration are already reported by the macros
+        let config = crate::util::get_config().cloned().unwrap_or_default();
+        let out_dir = out_dir(&profile_dir, crate::util::get_config_root(), &config);
+        Collector::new(profile_dir)
+            .out_dir(out_dir)
+            .packages(graph.packages)
+            .apps(graph.apps)
+            .config(config)
+            .collect()
+            .expect("Failed to generate css files of yew-style-in-rs");
     }
 }
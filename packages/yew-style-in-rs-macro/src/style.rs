@@ -6,27 +6,72 @@ use std::collections::HashSet;
 mod css;
 mod dyn_css;
 mod dyn_keyframes;
+mod dyn_style;
 mod keyframes;
+mod variants;
+
+pub use dyn_style::DynStyle;
 
 mod kw {
+    syn::custom_keyword!(atomic);
     syn::custom_keyword!(filename);
+    syn::custom_keyword!(variants);
+}
+
+// Generate checks that warn when a raw float is interpolated
+// right before a unit suffix like `${x}px`.
+//
+// The checks are spanned to the interpolated identifiers,
+// so the deprecation warning points at the css literal.
+fn unit_suffix_checks(idents: &[syn::Ident]) -> TokenStream {
+    let mut tokens = TokenStream::new();
+    for ident in idents {
+        tokens.append_all(quote::quote_spanned! {ident.span()=>
+            ::yew_style_in_rs::units::__private::UnitSuffixCheck(&#ident).check();
+        });
+    }
+    if tokens.is_empty() {
+        return tokens;
+    }
+    quote! {{
+        #[allow(unused_imports)]
+        use ::yew_style_in_rs::units::__private::UnitSuffixFallback as _;
+        #tokens
+    }}
 }
 
 // --- CSS Declaration ---
 
-// Parse filename setting.
+// Parse settings of css! macro.
 //
 // eg)
 // filename = "filename"
-struct Filename {
-    filename: syn::LitStr,
+//
+// eg)
+// atomic, filename = "filename"
+struct CssSettings {
+    filename: Option<syn::LitStr>,
+    atomic: bool,
 }
-impl syn::parse::Parse for Filename {
+impl syn::parse::Parse for CssSettings {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        input.parse::<kw::filename>()?;
-        input.parse::<syn::Token![=]>()?;
-        let filename: syn::LitStr = input.parse()?;
-        Ok(Self { filename })
+        let mut filename = None;
+        let mut atomic = false;
+        while !input.is_empty() {
+            if input.peek(kw::atomic) {
+                input.parse::<kw::atomic>()?;
+                atomic = true;
+            } else {
+                input.parse::<kw::filename>()?;
+                input.parse::<syn::Token![=]>()?;
+                filename = Some(input.parse()?);
+            }
+            if input.is_empty() {
+                break;
+            }
+            input.parse::<syn::Token![,]>()?;
+        }
+        Ok(Self { filename, atomic })
     }
 }
 
@@ -36,15 +81,18 @@ impl syn::parse::Parse for Filename {
 // css!(filename = "filename")
 //
 // eg)
+// css!(atomic)
+//
+// eg)
 // css! {" some style... "}
 enum CssMacro {
-    Filename(Filename),
+    Settings(CssSettings),
     CssMacro(css::Css),
 }
 impl syn::parse::Parse for CssMacro {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        if input.peek(kw::filename) {
-            Ok(Self::Filename(input.parse()?))
+        if input.peek(kw::filename) || input.peek(kw::atomic) {
+            Ok(Self::Settings(input.parse()?))
         } else {
             Ok(Self::CssMacro(input.parse()?))
         }
@@ -60,23 +108,35 @@ impl syn::parse::Parse for CssMacro {
 // let <ident> = css!(filename = "filename") {" some style... "};
 //
 // eg)
+// let <ident> = css!(atomic) {" some style... "};
+//
+// eg)
 // let <ident> = dyn css! {" some style... "};
+//
+// eg)
+// let <ident> = variants! { <arg>: <Enum> { <pattern> => " some style... ", ... }, ... };
 enum CssDeclaration {
     Css {
         ident: syn::Ident,
         filename: Option<syn::LitStr>,
+        atomic: bool,
         css: css::Css,
     },
     DynCss {
         ident: syn::Ident,
         dyn_css: dyn_css::DynCss,
     },
+    Variants {
+        ident: syn::Ident,
+        variants: variants::Variants,
+    },
 }
 impl CssDeclaration {
     fn ident(&self) -> syn::Ident {
         match self {
             Self::Css { ident, .. } => ident.clone(),
             Self::DynCss { ident, .. } => ident.clone(),
+            Self::Variants { ident, .. } => ident.clone(),
         }
     }
 
@@ -90,15 +150,23 @@ impl CssDeclaration {
             Self::Css {
                 ident,
                 filename,
+                atomic,
                 css,
             } => {
-                let css = css.clone().expand(filename, animation_names);
+                let css =
+                    css.clone()
+                        .expand(&ident.to_string(), filename, *atomic, animation_names);
                 tokens.append_all(quote! (let #ident = #css;))
             }
             Self::DynCss { ident, dyn_css } => {
-                let dyn_css = dyn_css.expand(animation_names, dyn_animation_names);
+                let dyn_css =
+                    dyn_css.expand(&ident.to_string(), animation_names, dyn_animation_names);
                 tokens.append_all(quote!(let #ident = #dyn_css;))
             }
+            Self::Variants { ident, variants } => {
+                let variants = variants.expand(&ident.to_string(), &None, animation_names);
+                tokens.append_all(quote!(let #ident = #variants;))
+            }
         }
         tokens
     }
@@ -115,23 +183,28 @@ impl syn::parse::Parse for CssDeclaration {
             let dyn_css: dyn_css::DynCss = dyn_css_macro.parse_body()?;
             input.parse::<syn::Token![;]>()?;
             Ok(Self::DynCss { ident, dyn_css })
+        } else if input.peek(kw::variants) && input.peek2(syn::Token![!]) {
+            let variants_macro: syn::Macro = input.parse()?;
+            let variants: variants::Variants = variants_macro.parse_body()?;
+            input.parse::<syn::Token![;]>()?;
+            Ok(Self::Variants { ident, variants })
         } else {
             let css_macro: syn::Macro = input.parse()?;
             let css_macro: CssMacro = css_macro.parse_body()?;
-            let (filename, css) = match css_macro {
-                CssMacro::Filename(filename) => {
-                    let filename = Some(filename.filename);
+            let (filename, atomic, css) = match css_macro {
+                CssMacro::Settings(settings) => {
                     let css;
                     syn::braced!(css in input);
                     let css: css::Css = css.parse()?;
-                    (filename, css)
+                    (settings.filename, settings.atomic, css)
                 }
-                CssMacro::CssMacro(css) => (None, css),
+                CssMacro::CssMacro(css) => (None, false, css),
             };
             input.parse::<syn::Token![;]>()?;
             Ok(Self::Css {
                 ident,
                 filename,
+                atomic,
                 css,
             })
         }
@@ -178,9 +251,38 @@ impl syn::parse::Parse for Style {
     }
 }
 impl Style {
+    // identifiers of the css declarations in order
+    pub fn idents(&self) -> Vec<syn::Ident> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                StyleItem::CssDeclaration(declaration) => Some(declaration.ident()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // arguments of the `variants!` declarations: (identifier, [(argument, type)])
+    pub fn variants(&self) -> Vec<(syn::Ident, Vec<(syn::Ident, syn::Type)>)> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                StyleItem::CssDeclaration(CssDeclaration::Variants { ident, variants }) => {
+                    Some((ident.clone(), variants.args()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn expand(&self) -> TokenStream {
         let mut content_tokens = TokenStream::new();
 
+        // rebuild when the configuration is edited
+        if crate::state::STATE.lock().unwrap().write_flag() {
+            content_tokens.append_all(crate::util::track_config());
+        }
+
         let mut css_declarations = vec![];
         let mut animation_names = vec![];
         let mut dyn_animation_names = vec![];
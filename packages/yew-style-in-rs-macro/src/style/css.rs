@@ -57,28 +57,44 @@ pub struct Css {
     code: syn::LitStr,
 }
 impl Css {
+    // `name` is the name of the declaration to hash the id.
+    // `atomic` is set by `css!(atomic)` to split top level properties into atomic classes.
     pub fn expand(
         self,
+        name: &str,
         filename: &Option<syn::LitStr>,
+        atomic: bool,
         animation_names: &Vec<RegisteredAnimationName>,
     ) -> TokenStream {
         use std::io::Write;
+        use yew_style_in_rs_build::ATOMIC_RULE_PREFIX;
         use yew_style_in_rs_core::ast::RuntimeCss;
+        use yew_style_in_rs_core::atomic::{atomic_class, atomic_rule, split_atomic};
         use yew_style_in_rs_core::transpiler::TranspiledCss;
 
         let mut state = STATE.lock().unwrap();
 
-        let id = if state.write_flag() {
-            let (id, mut file) = state
-                .create_random_id_file()
-                .expect("Failed to save internal file for yew-style-in-rs");
-
+        let (id, atomic_classes, scoped) = if state.write_flag() {
+            let config = match crate::util::get_config() {
+                Ok(config) => config,
+                Err(msg) => return quote!(std::compile_error!(#msg)),
+            };
             let filename = filename
                 .as_ref()
                 .map(|l| l.value())
-                .unwrap_or("style".into());
+                .unwrap_or_else(|| config.default_filename.clone());
+
+            let module_path = crate::util::module_path(self.code.span());
+            let content = format!("{filename}\n{}", self.code.value());
+            let (id, mut file) = state
+                .create_id_file(config, &module_path, name, &content)
+                .expect("Failed to save internal file for yew-style-in-rs");
+
             file.write(format!("{filename}\n").as_bytes())
                 .expect("Failed to save internal file for yew-style-in-rs");
+            fragment_meta(self.code.span(), name, vec![])
+                .write(&mut file)
+                .expect("Failed to save internal file for yew-style-in-rs");
 
             let code = self.code.value();
             let code = match replace_animation_name(code, animation_names) {
@@ -90,21 +106,53 @@ impl Css {
                 Ok(runtime_css) => runtime_css,
                 Err((_, msg)) => return quote!(std::compile_error!(#msg)),
             };
+            let runtime_css = crate::prefix::add_vendor_prefixes(runtime_css, &config.targets);
+
+            // In atomic mode, write top level properties as atomic rules
+            // and the rest as the scoped class.
+            let (atomic_properties, runtime_css) = if atomic {
+                split_atomic(runtime_css)
+            } else {
+                (vec![], runtime_css)
+            };
+            for property in &atomic_properties {
+                let rule = atomic_rule(property, &config.class_prefix);
+                file.write_all(format!("{ATOMIC_RULE_PREFIX}{rule}\n").as_bytes())
+                    .expect("Failed to save internal file for yew-style-in-rs");
+            }
+            let atomic_classes = atomic_properties
+                .iter()
+                .map(|property| atomic_class(property, &config.class_prefix))
+                .collect::<Vec<_>>();
+            let scoped = !runtime_css.0.is_empty();
+
             let transpiled_css = TranspiledCss::transpile(&[format!(".{id}")], runtime_css);
             let css = transpiled_css.to_style_string();
 
             file.write(css.as_bytes())
                 .expect("Failed to save internal file for yew-style-in-rs");
 
-            id
+            (id, atomic_classes, scoped)
         } else {
-            "dummy".into()
+            ("dummy".into(), vec![], true)
         };
 
-        quote!({
-            use ::yew_style_in_rs::css::StyleId;
-            StyleId::new(#id)
-        })
+        if atomic {
+            // `Classes` of the scoped class if any and the atomic classes
+            let scoped = scoped.then(|| quote!(classes.push(StyleId::new(#id));));
+            quote!({
+                use ::yew_style_in_rs::css::StyleId;
+                let mut classes = ::yew::Classes::new();
+                #scoped
+                #(classes.push(#atomic_classes);)*
+                classes
+            })
+        } else {
+            quote!({
+                use ::yew_style_in_rs::css::StyleId;
+                StyleId::new(#id)
+            })
+        }
     }
 }
 impl syn::parse::Parse for Css {
@@ -1,5 +1,5 @@
 use proc_macro2::{Span, TokenStream};
-use quote::{quote, TokenStreamExt};
+use quote::{quote, quote_spanned, TokenStreamExt};
 use std::collections::HashSet;
 
 use crate::cursor::*;
@@ -63,15 +63,51 @@ fn replace_animation_name(
 // When `parse()`, inspect code and replace `{` with `{{`, `}` with `}}`, `${ident}` with `{ident}`
 // and collect idents to use when expanding macro.
 // When `expand()`, generate code with idents using `format!` macro
-// and using `use_effect_with_deps` to register/unregister runtime manager
+// and using `hooks::use_dyn_style` to register/unregister runtime manager
 // when code is change or destroy this element.
 pub struct DynCss {
     code: syn::LitStr,
+    // span of the string literal for the source location in runtime errors
+    span: Span,
     idents: Vec<syn::Ident>,
+    // idents interpolated right before a unit suffix like `${x}px`
+    unit_suffixed_idents: Vec<syn::Ident>,
 }
 impl DynCss {
+    // named arguments of `format!` for the interpolated idents
+    fn dependencies(&self) -> TokenStream {
+        let mut dependencies = TokenStream::new();
+        let mut hashset = HashSet::new();
+        for ident in &self.idents {
+            hashset.insert(ident.to_string());
+        }
+        for ident in hashset.iter() {
+            let ident = syn::Ident::new(ident, Span::call_site());
+            dependencies.append_all(quote!(#ident = #ident, ));
+        }
+        dependencies
+    }
+
+    // Expand to the `String` of the code without registering it.
+    // Used by `dyn_style!`, which can not refer to animation names.
+    pub fn expand_code(&self) -> TokenStream {
+        let code = self.code.value();
+        if code.contains("##") {
+            let msg = "`##<animation_name>##` is not available in `dyn_style!`, write the animation name directly";
+            return quote!(std::compile_error!(#msg));
+        }
+        let dependencies = self.dependencies();
+        let unit_suffix_checks = super::unit_suffix_checks(&self.unit_suffixed_idents);
+        quote! {{
+            #unit_suffix_checks
+            format!(#code, #dependencies)
+        }}
+    }
+
+    // `name` is the name of the declaration for the readable id in debug builds.
     pub fn expand(
         &self,
+        name: &str,
         animation_names: &Vec<RegisteredAnimationName>,
         dyn_animation_names: &Vec<String>,
     ) -> TokenStream {
@@ -81,18 +117,7 @@ impl DynCss {
             Err(msg) => return quote!(std::compile_error!(#msg)),
         };
 
-        let dependencies = {
-            let mut dependencies = TokenStream::new();
-            let mut hashset = HashSet::new();
-            for ident in &self.idents {
-                hashset.insert(ident.to_string());
-            }
-            for ident in hashset.iter() {
-                let ident = syn::Ident::new(ident, Span::call_site());
-                dependencies.append_all(quote!(#ident = #ident, ));
-            }
-            dependencies
-        };
+        let dependencies = self.dependencies();
         let animation_names_vec = {
             let mut tokens = TokenStream::new();
             for name in animation_names {
@@ -101,105 +126,40 @@ impl DynCss {
             }
             quote!(vec![#tokens])
         };
+        let unit_suffix_checks = super::unit_suffix_checks(&self.unit_suffixed_idents);
+        let location = quote_spanned!(self.span=> concat!(file!(), ":", line!(), ":", column!()));
+        let debug_name = crate::util::debug_name(name);
 
         quote! {{
-            let prev_style_handle = ::yew::use_mut_ref(|| None);
-            let style_state = ::yew::use_state_eq(|| None);
+            #unit_suffix_checks
 
             let animation_names: Vec<String> = #animation_names_vec;
 
             let code = format!(#code, #dependencies);
-
-            // Unregister previous style and register new style when code is changed.
-            ::yew::use_effect_with_deps(
-                {
-                    let style_state = style_state.clone();
-                    move |(code, dyn_names_map): &(String, std::collections::HashMap<String, Vec<String>>)| {
-                        let manager = ::yew_style_in_rs::runtime_manager::StyleManager::default();
-
-                        let mut cursor = ::yew_style_in_rs::cursor::Cursor::new(code);
-                        let mut code = String::new();
-                        while !cursor.is_empty() {
-                            if cursor.peek('#') {
-                                cursor.take('#').unwrap();
-                                if cursor.peek('#') {
-                                    cursor.take('#').unwrap();
-                                    let name = match cursor.take_until('#') {
-                                        Ok(name) => name,
-                                        Err(_) => break,
-                                    };
-
-                                    if let Some(name) = animation_names.iter().find(|&n| n == &name) {
-                                        code += &name;
-                                    } else {
-                                        if let Some((id, _)) = dyn_names_map.iter().find(|(_, n)| n.contains(&name)) {
-                                            code += &name;
-                                            code += "-";
-                                            code += id;
-                                        } else {
-                                            code += "##";
-                                            code += &name;
-                                            code += "##";
-                                        }
-                                    }
-
-                                    cursor.take('#').unwrap();
-                                    if cursor.peek('#') {
-                                        cursor.take('#').unwrap();
-                                    }
-                                } else {
-                                    code.push('#');
-                                }
-                            } else {
-                                let ch = cursor.next().unwrap();
-                                code.push(ch);
-                            }
-                        }
-
-                        let style = manager.register(code);
-                        if let Some(style) = prev_style_handle.borrow().clone() {
-                            manager.unregister(style);
-                        }
-                        *prev_style_handle.borrow_mut() = Some(style.clone());
-                        style_state.set(Some(style));
-                        || ()
-                    }
-                },
-                (code, dyn_names_map.clone())
-            );
-
-            // Unregister style when destroy elements.
-            ::yew::use_effect_with_deps(
-                {
-                    let style_state = style_state.clone();
-                    move |_| {
-                        let manager = ::yew_style_in_rs::runtime_manager::StyleManager::default();
-                        let style_state = style_state.clone();
-                        move || {
-                            if let Some(style) = (*style_state).as_ref() {
-                                manager.unregister(style.clone());
-                            }
-                        }
-                    }
-                },
-                ()
-            );
+            let code = ::yew_style_in_rs::dyn_css::resolve_animation_names(&code, &animation_names, &dyn_names_map);
 
             // return `dyn_css::StyleId` of current style.
-            // If no style, return empty `StyleId`
-            (*style_state).as_ref().map_or_else(|| ::yew_style_in_rs::dyn_css::StyleId::new(""), |s| s.style_id())
+            // Parse errors of the code are reported and the id is readable in debug builds.
+            ::yew_style_in_rs::hooks::use_dyn_style(
+                code,
+                ::yew_style_in_rs::hooks::DynStyleKind::Css,
+                if cfg!(debug_assertions) { Some(#location.to_string()) } else { None },
+                if cfg!(debug_assertions) { Some(#debug_name) } else { None },
+            )
         }}
     }
 }
 impl syn::parse::Parse for DynCss {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         let raw_code: syn::LitStr = input.parse()?;
+        let span = raw_code.span();
         let raw_code = raw_code.value();
 
         let mut cursor = Cursor::new(&raw_code);
 
         let mut code = String::new();
         let mut idents = vec![];
+        let mut unit_suffixed_idents = vec![];
         while !cursor.is_empty() {
             if cursor.peek('$') {
                 cursor.take('$');
@@ -208,6 +168,9 @@ impl syn::parse::Parse for DynCss {
                     code.push_str(&format!("{{{ident}}}"));
 
                     let ident = syn::Ident::new(&ident, input.span());
+                    if cursor.peek_with(|c| c.is_ascii_alphabetic() || c == '%') {
+                        unit_suffixed_idents.push(ident.clone());
+                    }
                     idents.push(ident);
                 } else {
                     code.push('$');
@@ -225,6 +188,11 @@ impl syn::parse::Parse for DynCss {
         }
         let code = syn::LitStr::new(&code, input.span());
 
-        Ok(Self { code, idents })
+        Ok(Self {
+            code,
+            span,
+            idents,
+            unit_suffixed_idents,
+        })
     }
 }
@@ -20,7 +20,7 @@ mod kw {
 // "#}
 //
 // When `expand()`, generate code with idents using `format!` macro
-// and using `use_effect_with_deps` to register/unregister runtime manager
+// and using `hooks::use_dyn_style` to register/unregister runtime manager
 // when code is change or destroy this element.
 // `expand()` also return animation names vec.
 pub struct DynKeyframes {
@@ -102,6 +102,7 @@ impl DynKeyframes {
         let mut cursor = Cursor::new(&raw_code);
         let mut code = String::new();
         let mut idents = vec![];
+        let mut unit_suffixed_idents = vec![];
         while !cursor.is_empty() {
             if cursor.peek('$') {
                 cursor.take('$');
@@ -110,6 +111,9 @@ impl DynKeyframes {
                     code.push_str(&format!("{{{ident}}}"));
 
                     let ident = syn::Ident::new(&ident, self.code.span());
+                    if cursor.peek_with(|c| c.is_ascii_alphabetic() || c == '%') {
+                        unit_suffixed_idents.push(ident.clone());
+                    }
                     idents.push(ident);
                 } else {
                     code.push('$');
@@ -146,49 +150,20 @@ impl DynKeyframes {
             }
             quote!(vec![#tokens])
         };
+        let unit_suffix_checks = super::unit_suffix_checks(&unit_suffixed_idents);
+        let debug_name = crate::util::debug_name("keyframes");
 
         let tokens = quote! {{
-            let prev_style_handle = ::yew::use_mut_ref(|| None);
-            let style_state = ::yew::use_state_eq(|| None);
+            #unit_suffix_checks
 
             let code = format!(#code, #dependencies);
 
-            // Unregister previous style and register new style when code is changed.
-            ::yew::use_effect_with_deps(
-                {
-                    let style_state = style_state.clone();
-                    move |code: &String| {
-                        let manager = ::yew_style_in_rs::runtime_manager::StyleManager::default();
-                        let style = manager.register_dyn_keyframes(code.to_string());
-                        if let Some(style) = prev_style_handle.borrow().clone() {
-                            manager.unregister(style);
-                        }
-                        *prev_style_handle.borrow_mut() = Some(style.clone());
-                        style_state.set(Some(style));
-                        || ()
-                    }
-                },
-                code
-            );
-
-            // Unregister style when destroy elements.
-            ::yew::use_effect_with_deps(
-                {
-                    let style_state = style_state.clone();
-                    move |_| {
-                        let manager = ::yew_style_in_rs::runtime_manager::StyleManager::default();
-                        let style_state = style_state.clone();
-                        move || {
-                            if let Some(style) = (*style_state).as_ref() {
-                                manager.unregister(style.clone());
-                            }
-                        }
-                    }
-                },
-                ()
+            let style_id = ::yew_style_in_rs::hooks::use_dyn_style(
+                code,
+                ::yew_style_in_rs::hooks::DynStyleKind::Keyframes,
+                None,
+                if cfg!(debug_assertions) { Some(#debug_name) } else { None },
             );
-
-            let style_id = (*style_state).as_ref().map_or_else(|| ::yew_style_in_rs::dyn_css::StyleId::new(""), |s| s.style_id());
             dyn_names_map.insert(style_id.id().to_string(), #animation_names_vec);
         }};
 
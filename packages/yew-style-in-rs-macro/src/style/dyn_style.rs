@@ -0,0 +1,47 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+
+use crate::style::dyn_css::DynCss;
+
+// Parse `dyn_style!` macro.
+//
+// eg)
+// dyn_style!(r#"width: ${width}px;"#)
+// expands to `DynStyle::with_name(code, name)`.
+// The id has the readable name `<crate>__dyn_style` in debug builds.
+//
+// eg)
+// dyn_style!(self.style, r#"width: ${width}px;"#)
+// expands to `self.style.update(code)`.
+pub struct DynStyle {
+    target: Option<syn::Expr>,
+    dyn_css: DynCss,
+}
+impl DynStyle {
+    pub fn expand(&self) -> TokenStream {
+        let code = self.dyn_css.expand_code();
+        let debug_name = crate::util::debug_name("dyn_style");
+        match &self.target {
+            Some(target) => quote!((#target).update(#code)),
+            None => quote! {
+                ::yew_style_in_rs::dyn_style::DynStyle::with_name(
+                    #code,
+                    if cfg!(debug_assertions) { Some(#debug_name) } else { None },
+                )
+            },
+        }
+    }
+}
+impl syn::parse::Parse for DynStyle {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let target = if input.peek(syn::LitStr) {
+            None
+        } else {
+            let target: syn::Expr = input.parse()?;
+            input.parse::<syn::Token![,]>()?;
+            Some(target)
+        };
+        let dyn_css: DynCss = input.parse()?;
+        Ok(Self { target, dyn_css })
+    }
+}
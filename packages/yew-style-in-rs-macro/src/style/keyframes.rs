@@ -81,8 +81,16 @@ impl Keyframes {
         let mut state = STATE.lock().unwrap();
 
         let anim_names = if state.write_flag() {
+            let config = crate::util::get_config()?;
+            let filename = self
+                .filename
+                .as_ref()
+                .map(|l| l.value())
+                .unwrap_or_else(|| config.default_filename.clone());
+            let module_path = crate::util::module_path(self.code.span());
+            let content = format!("{filename}\n{}", self.code.value());
             let (id, mut file) = state
-                .create_random_id_file()
+                .create_id_file(config, &module_path, "keyframes", &content)
                 .expect("Failed to save internal file for yew-style-in-rs");
 
             let code = self.code.value();
@@ -156,13 +164,15 @@ impl Keyframes {
                 cursor.skip_white_space();
             }
 
-            let filename = self
-                .filename
-                .as_ref()
-                .map(|l| l.value())
-                .unwrap_or("style".into());
             file.write(format!("{filename}\n").as_bytes())
                 .expect("Failed to save internal file for yew-style-in-rs");
+            let keyframes = anim_names
+                .iter()
+                .map(|name| name.animation_name_with_scoped_id.clone())
+                .collect();
+            fragment_meta(self.code.span(), "keyframes", keyframes)
+                .write(&mut file)
+                .expect("Failed to save internal file for yew-style-in-rs");
 
             file.write(code.as_bytes())
                 .expect("Failed to save internal file for yew-style-in-rs");
@@ -0,0 +1,130 @@
+use proc_macro2::TokenStream;
+use quote::{quote, TokenStreamExt};
+
+use crate::style::css::Css;
+use crate::style::keyframes::RegisteredAnimationName;
+
+// Parse an arm of a variant axis.
+//
+// eg)
+// Size::Small => "font-size: 12px;"
+struct VariantArm {
+    pat: syn::Pat,
+    css: Css,
+}
+impl syn::parse::Parse for VariantArm {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        // `A | B` patterns
+        let mut cases = syn::punctuated::Punctuated::<syn::Pat, syn::Token![|]>::new();
+        cases.push_value(input.parse()?);
+        while input.peek(syn::Token![|]) {
+            cases.push_punct(input.parse()?);
+            cases.push_value(input.parse()?);
+        }
+        let pat = if cases.len() == 1 {
+            cases.pop().unwrap().into_value()
+        } else {
+            syn::Pat::Or(syn::PatOr {
+                attrs: vec![],
+                leading_vert: None,
+                cases,
+            })
+        };
+        input.parse::<syn::Token![=>]>()?;
+        let css: Css = input.parse()?;
+        Ok(Self { pat, css })
+    }
+}
+
+// Parse a variant axis keyed on a Rust enum.
+//
+// eg)
+// size: Size {
+//     Size::Small => "font-size: 12px;",
+//     Size::Large => "font-size: 16px;",
+// }
+struct VariantAxis {
+    arg: syn::Ident,
+    ty: syn::Type,
+    arms: Vec<VariantArm>,
+}
+impl syn::parse::Parse for VariantAxis {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let arg: syn::Ident = input.parse()?;
+        input.parse::<syn::Token![:]>()?;
+        let ty: syn::Type = input.parse()?;
+        let content;
+        syn::braced!(content in input);
+        let arms = content.parse_terminated::<_, syn::Token![,]>(VariantArm::parse)?;
+        Ok(Self {
+            arg,
+            ty,
+            arms: arms.into_iter().collect(),
+        })
+    }
+}
+
+// Parse the body of `variants!` declaration.
+//
+// Every fragment is transpiled and written to the css file as `css!`,
+// and the declaration expands to `fn(<axis types>) -> Classes`
+// matching the arguments exhaustively.
+pub struct Variants {
+    axes: Vec<VariantAxis>,
+}
+impl Variants {
+    // arguments of the declared function: (argument, type) of the axes in order
+    pub fn args(&self) -> Vec<(syn::Ident, syn::Type)> {
+        self.axes
+            .iter()
+            .map(|axis| (axis.arg.clone(), axis.ty.clone()))
+            .collect()
+    }
+
+    // `name` is the name of the declaration to hash the ids.
+    pub fn expand(
+        &self,
+        name: &str,
+        filename: &Option<syn::LitStr>,
+        animation_names: &Vec<RegisteredAnimationName>,
+    ) -> TokenStream {
+        let mut args = vec![];
+        let mut tys = vec![];
+        let mut pushes = TokenStream::new();
+        for axis in &self.axes {
+            let arg = &axis.arg;
+            let mut arms = TokenStream::new();
+            for arm in &axis.arms {
+                let pat = &arm.pat;
+                let arm_name = format!("{name}::{}", quote!(#pat)).replace(' ', "");
+                let css = arm
+                    .css
+                    .clone()
+                    .expand(&arm_name, filename, false, animation_names);
+                arms.append_all(quote!(#pat => #css,));
+            }
+            pushes.append_all(quote!(classes.push(match #arg { #arms });));
+            args.push(arg);
+            tys.push(&axis.ty);
+        }
+        quote! {{
+            let variants: fn(#(#tys),*) -> ::yew::Classes = |#(#args: #tys),*| {
+                let mut classes = ::yew::Classes::new();
+                #pushes
+                classes
+            };
+            variants
+        }}
+    }
+}
+impl syn::parse::Parse for Variants {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let axes = input.parse_terminated::<_, syn::Token![,]>(VariantAxis::parse)?;
+        if axes.is_empty() {
+            return Err(input.error("`variants!` requires at least one axis"));
+        }
+        Ok(Self {
+            axes: axes.into_iter().collect(),
+        })
+    }
+}
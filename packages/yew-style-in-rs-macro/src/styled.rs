@@ -0,0 +1,299 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, TokenStreamExt};
+
+use crate::style::Style;
+
+// HTML attributes forwarded to the element: (prop name, attribute name).
+const ATTRIBUTES: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("style", "style"),
+    ("title", "title"),
+    ("role", "role"),
+    ("tabindex", "tabindex"),
+    ("aria_label", "aria-label"),
+];
+
+// Event listeners forwarded to the element: (prop name, event type).
+const LISTENERS: &[(&str, &str)] = &[
+    ("onclick", "MouseEvent"),
+    ("ondblclick", "MouseEvent"),
+    ("onmousedown", "MouseEvent"),
+    ("onmouseup", "MouseEvent"),
+    ("onmouseenter", "MouseEvent"),
+    ("onmouseleave", "MouseEvent"),
+    ("onfocus", "FocusEvent"),
+    ("onblur", "FocusEvent"),
+    ("onkeydown", "KeyboardEvent"),
+    ("onkeyup", "KeyboardEvent"),
+];
+
+// Elements which have the `disabled` attribute.
+const FORM_ELEMENTS: &[&str] = &[
+    "button", "fieldset", "input", "optgroup", "option", "select", "textarea",
+];
+
+// Elements which can not have children.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+// Parse `styled!` macro.
+//
+// eg)
+// styled! {
+//     pub Button: button {
+//         let css = css! {"color: red;"};
+//     }
+// }
+//
+// eg)
+// styled! {
+//     pub Bar: div(width: Px) {
+//         let css = css! {"display: block;"};
+//         let dyn_css = dyn css! {r#"width: ${width};"#};
+//     }
+// }
+//
+// eg)
+// styled! {
+//     pub Link: a[href, target, oncontextmenu: MouseEvent] {
+//         let css = css! {"color: red;"};
+//     }
+// }
+//
+// The body is the same as `style!`.
+// The declared classes are applied in order, followed by the `class` prop.
+// The arguments of `variants!` declarations are added to the props,
+// and the classes of the variants are applied with the props.
+//
+// Attributes in brackets are forwarded in addition to `ATTRIBUTES` and `LISTENERS`,
+// and `disabled` of `FORM_ELEMENTS`.
+// `_` in the names is replaced with `-` like `aria_describedby`,
+// and the names with event types are forwarded as listeners.
+pub struct Styled {
+    attrs: Vec<syn::Attribute>,
+    vis: syn::Visibility,
+    name: syn::Ident,
+    tag: syn::Ident,
+    disabled: bool,
+    extra_attributes: Vec<(syn::Ident, Option<syn::Type>)>,
+    fields: Vec<(syn::Ident, syn::Type)>,
+    body: TokenStream,
+    idents: Vec<syn::Ident>,
+    variants: Vec<(syn::Ident, Vec<(syn::Ident, syn::Type)>)>,
+}
+impl Styled {
+    pub fn expand(&self) -> TokenStream {
+        let Self {
+            attrs,
+            vis,
+            name,
+            tag,
+            body,
+            ..
+        } = self;
+        let props_name = format_ident!("{}Props", name);
+        let fn_name = format_ident!("__styled_{}", name);
+
+        let mut field_tokens = TokenStream::new();
+        let mut bindings = TokenStream::new();
+        for (ident, ty) in &self.fields {
+            field_tokens.append_all(quote!(pub #ident: #ty,));
+            // bind the fields so that `dyn css!` can interpolate them
+            bindings.append_all(quote! {
+                #[allow(unused_variables)]
+                let #ident = props.#ident.clone();
+            });
+        }
+
+        let mut element_attrs = TokenStream::new();
+        for (prop, attribute) in ATTRIBUTES {
+            let prop = format_ident!("{}", prop);
+            field_tokens.append_all(quote! {
+                #[prop_or_default]
+                pub #prop: Option<String>,
+            });
+            // `aria-label` is not an identifier, so parse the name as tokens
+            let attribute: TokenStream = attribute.parse().unwrap();
+            element_attrs.append_all(quote!(#attribute={props.#prop.clone()}));
+        }
+        for (prop, event) in LISTENERS {
+            let prop = format_ident!("{}", prop);
+            let event = format_ident!("{}", event);
+            field_tokens.append_all(quote! {
+                #[prop_or_default]
+                pub #prop: Option<::yew::Callback<::yew::events::#event>>,
+            });
+            element_attrs.append_all(quote!(#prop={props.#prop.clone()}));
+        }
+        for (prop, event) in &self.extra_attributes {
+            match event {
+                Some(event) => {
+                    field_tokens.append_all(quote! {
+                        #[prop_or_default]
+                        pub #prop: Option<::yew::Callback<#event>>,
+                    });
+                    element_attrs.append_all(quote!(#prop={props.#prop.clone()}));
+                }
+                None => {
+                    field_tokens.append_all(quote! {
+                        #[prop_or_default]
+                        pub #prop: Option<String>,
+                    });
+                    let attribute: TokenStream =
+                        prop.to_string().replace('_', "-").parse().unwrap();
+                    element_attrs.append_all(quote!(#attribute={props.#prop.clone()}));
+                }
+            }
+        }
+        if self.disabled {
+            field_tokens.append_all(quote! {
+                #[prop_or_default]
+                pub disabled: bool,
+            });
+            element_attrs.append_all(quote!(disabled = { props.disabled }));
+        }
+
+        // `variants!` declarations are functions of the arguments,
+        // so add the arguments to the props unless the fields have the same names
+        let mut classes = vec![];
+        let mut variant_args = vec![];
+        for ident in &self.idents {
+            match self.variants.iter().find(|(variant, _)| variant == ident) {
+                Some((_, args)) => {
+                    for (arg, ty) in args {
+                        let declared = self.fields.iter().any(|(field, _)| field == arg)
+                            || variant_args.contains(arg);
+                        if !declared {
+                            field_tokens.append_all(quote!(pub #arg: #ty,));
+                            variant_args.push(arg.clone());
+                        }
+                    }
+                    let args = args.iter().map(|(arg, _)| arg);
+                    classes.push(quote!(#ident(#(props.#args.clone()),*)));
+                }
+                None => classes.push(quote!(#ident)),
+            }
+        }
+
+        let element = if VOID_ELEMENTS.contains(&tag.to_string().as_str()) {
+            quote!(<#tag class={class} #element_attrs />)
+        } else {
+            quote! {
+                <#tag class={class} #element_attrs>
+                    { for props.children.iter() }
+                </#tag>
+            }
+        };
+
+        quote! {
+            #[derive(::yew::Properties, PartialEq)]
+            #vis struct #props_name {
+                #field_tokens
+                #[prop_or_default]
+                pub children: ::yew::Children,
+                #[prop_or_default]
+                pub class: ::yew::Classes,
+            }
+
+            #(#attrs)*
+            #[::yew::function_component(#name)]
+            #[allow(non_snake_case)]
+            #vis fn #fn_name(props: &#props_name) -> ::yew::Html {
+                #bindings
+                ::yew_style_in_rs::style! { #body }
+                let class = ::yew::classes!(#(#classes,)* props.class.clone());
+                ::yew::html! { #element }
+            }
+        }
+    }
+}
+impl syn::parse::Parse for Styled {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let vis: syn::Visibility = input.parse()?;
+        let name: syn::Ident = input.parse()?;
+        input.parse::<syn::Token![:]>()?;
+        let tag: syn::Ident = input.parse()?;
+
+        // `disabled` is forwarded to form elements, or to others if declared in brackets
+        let mut disabled = FORM_ELEMENTS.contains(&tag.to_string().as_str());
+        let mut extra_attributes = vec![];
+        if input.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in input);
+            while !content.is_empty() {
+                let prop: syn::Ident = content.parse()?;
+                let event = if content.peek(syn::Token![:]) {
+                    content.parse::<syn::Token![:]>()?;
+                    Some(content.parse::<syn::Type>()?)
+                } else {
+                    None
+                };
+                let forwarded = ATTRIBUTES.iter().any(|(name, _)| prop == name)
+                    || LISTENERS.iter().any(|(name, _)| prop == name)
+                    || ["children", "class"].iter().any(|name| prop == name)
+                    || prop == "disabled" && disabled;
+                if forwarded {
+                    return Err(syn::Error::new(
+                        prop.span(),
+                        format!("`{prop}` is already forwarded by `styled!`"),
+                    ));
+                }
+                // `disabled` is a boolean attribute
+                if prop == "disabled" && event.is_none() {
+                    disabled = true;
+                } else {
+                    extra_attributes.push((prop, event));
+                }
+                if content.is_empty() {
+                    break;
+                }
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        let mut fields = vec![];
+        if input.peek(syn::token::Paren) {
+            let content;
+            syn::parenthesized!(content in input);
+            while !content.is_empty() {
+                let ident: syn::Ident = content.parse()?;
+                content.parse::<syn::Token![:]>()?;
+                let ty: syn::Type = content.parse()?;
+                fields.push((ident, ty));
+                if content.is_empty() {
+                    break;
+                }
+                content.parse::<syn::Token![,]>()?;
+            }
+        }
+
+        let content;
+        syn::braced!(content in input);
+        let body: TokenStream = content.parse()?;
+        let style: Style = syn::parse2(body.clone())?;
+        let idents = style.idents();
+        let variants = style.variants();
+        if idents.is_empty() {
+            return Err(syn::Error::new(
+                name.span(),
+                "`styled!` requires at least one css declaration",
+            ));
+        }
+
+        Ok(Self {
+            attrs,
+            vis,
+            name,
+            tag,
+            disabled,
+            extra_attributes,
+            fields,
+            body,
+            idents,
+            variants,
+        })
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::BTreeMap;
+use yew_style_in_rs_build::{Browser, Version};
+use yew_style_in_rs_core::ast::*;
+use yew_style_in_rs_core::transpiler::TranspiledCss;
+
+use super::prefix::add_vendor_prefixes;
+use super::styled::Styled;
+
+// Expand `styled!` to the string of tokens.
+fn expand_styled(tokens: &str) -> String {
+    let styled: Styled = syn::parse_str(tokens).unwrap();
+    styled.expand().to_string()
+}
+
+#[test]
+fn test_styled_1() {
+    // `disabled` is forwarded to form elements only
+    let button = expand_styled(r#"pub Button: button { let css = css! {"color: red;"}; }"#);
+    assert!(button.contains("pub disabled : bool"));
+    assert!(button.contains("disabled = { props . disabled }"));
+
+    let div = expand_styled(r#"pub Panel: div { let css = css! {"color: red;"}; }"#);
+    assert!(!div.contains("disabled"));
+
+    let div = expand_styled(r#"pub Panel: div[disabled] { let css = css! {"color: red;"}; }"#);
+    assert!(div.contains("pub disabled : bool"));
+}
+
+#[test]
+fn test_styled_2() {
+    // `variants!` declarations are applied with the props of the arguments
+    let expanded = expand_styled(
+        r#"pub Button: button(size: Size) {
+            let css = css! {"color: red;"};
+            let variant = variants! {
+                size: Size { Size::Small => "font-size: 12px;", Size::Large => "font-size: 16px;" },
+                tone: Tone { Tone::Primary => "color: blue;", Tone::Danger => "color: red;" },
+            };
+        }"#,
+    );
+    assert!(expanded.contains(
+        "classes ! (css , variant (props . size . clone () , props . tone . clone ()) , props . class . clone ())"
+    ));
+    // `size` is the declared field, and `tone` is added to the props
+    assert_eq!(expanded.matches("pub size : Size").count(), 1);
+    assert!(expanded.contains("pub tone : Tone"));
+}
+
+#[test]
+fn test_styled_3() {
+    // attributes in brackets are forwarded in addition to the common ones
+    let expanded = expand_styled(
+        r#"pub Link: a[href, aria_describedby, oncontextmenu: MouseEvent] {
+            let css = css! {"color: red;"};
+        }"#,
+    );
+    assert!(expanded.contains("pub href : Option < String >"));
+    assert!(expanded.contains("href = { props . href . clone () }"));
+    assert!(expanded.contains("aria - describedby = { props . aria_describedby . clone () }"));
+    assert!(expanded.contains("pub oncontextmenu : Option < :: yew :: Callback < MouseEvent >>"));
+    assert!(expanded.contains("oncontextmenu = { props . oncontextmenu . clone () }"));
+
+    // the common attributes can not be declared again
+    let error = syn::parse_str::<Styled>(r#"pub Link: a[id] { let css = css! {"color: red;"}; }"#)
+        .err()
+        .unwrap();
+    assert_eq!(error.to_string(), "`id` is already forwarded by `styled!`");
+}
+
+fn parse(code: &str) -> RuntimeCss {
+    RuntimeCss::parse(code).unwrap_or_else(|(_, msg)| panic!("{msg}"))
+}
+
+// Transpiled css of the code with the vendor prefixes for the targets.
+fn transpile(code: &str, targets: &BTreeMap<Browser, Version>) -> String {
+    let runtime_css = add_vendor_prefixes(parse(code), targets);
+    TranspiledCss::transpile(&[".a".to_string()], runtime_css).to_style_string()
+}
+
+// Properties of the code with the vendor prefixes for the target.
+fn prefixed(code: &str, browser: Browser, major: u32, minor: u32) -> Vec<String> {
+    let targets = BTreeMap::from([(browser, Version::new(major, minor))]);
+    add_vendor_prefixes(parse(code), &targets)
+        .0
+        .into_iter()
+        .map(|declaration| match declaration {
+            Declaration::Property(property) => property.property,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+#[test]
+fn test_prefix_1() {
+    // `appearance` is prefixed for every browser before the standard support
+    let code = "appearance: none;";
+    assert_eq!(
+        prefixed(code, Browser::Chrome, 83, 0),
+        vec!["-webkit-appearance", "appearance"]
+    );
+    assert_eq!(prefixed(code, Browser::Chrome, 84, 0), vec!["appearance"]);
+    assert_eq!(
+        prefixed(code, Browser::Edge, 83, 0),
+        vec!["-webkit-appearance", "appearance"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Safari, 15, 3),
+        vec!["-webkit-appearance", "appearance"]
+    );
+    assert_eq!(prefixed(code, Browser::Safari, 15, 4), vec!["appearance"]);
+    assert_eq!(
+        prefixed(code, Browser::Firefox, 79, 0),
+        vec!["-moz-appearance", "appearance"]
+    );
+    assert_eq!(prefixed(code, Browser::Firefox, 80, 0), vec!["appearance"]);
+}
+
+#[test]
+fn test_prefix_2() {
+    // `backdrop-filter` and `hyphens` are prefixed for safari only
+    let code = "backdrop-filter: blur(4px);";
+    assert_eq!(
+        prefixed(code, Browser::Safari, 17, 0),
+        vec!["-webkit-backdrop-filter", "backdrop-filter"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Safari, 18, 0),
+        vec!["backdrop-filter"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Chrome, 70, 0),
+        vec!["backdrop-filter"]
+    );
+
+    let code = "hyphens: auto;";
+    assert_eq!(
+        prefixed(code, Browser::Safari, 16, 0),
+        vec!["-webkit-hyphens", "hyphens"]
+    );
+    assert_eq!(prefixed(code, Browser::Safari, 17, 0), vec!["hyphens"]);
+    assert_eq!(prefixed(code, Browser::Firefox, 40, 0), vec!["hyphens"]);
+}
+
+#[test]
+fn test_prefix_3() {
+    // `mask-image` is prefixed for chromium and safari
+    let code = "mask-image: url(mask.svg);";
+    assert_eq!(
+        prefixed(code, Browser::Chrome, 119, 0),
+        vec!["-webkit-mask-image", "mask-image"]
+    );
+    assert_eq!(prefixed(code, Browser::Chrome, 120, 0), vec!["mask-image"]);
+    assert_eq!(
+        prefixed(code, Browser::Edge, 119, 0),
+        vec!["-webkit-mask-image", "mask-image"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Safari, 15, 3),
+        vec!["-webkit-mask-image", "mask-image"]
+    );
+    assert_eq!(prefixed(code, Browser::Firefox, 50, 0), vec!["mask-image"]);
+}
+
+#[test]
+fn test_prefix_4() {
+    // `text-size-adjust` is not supported without the prefix in safari
+    let code = "text-size-adjust: 100%;";
+    assert_eq!(
+        prefixed(code, Browser::Safari, 99, 0),
+        vec!["-webkit-text-size-adjust", "text-size-adjust"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Chrome, 50, 0),
+        vec!["text-size-adjust"]
+    );
+
+    // `user-select` is prefixed for every browser, and always for safari
+    let code = "user-select: none;";
+    assert_eq!(
+        prefixed(code, Browser::Chrome, 53, 0),
+        vec!["-webkit-user-select", "user-select"]
+    );
+    assert_eq!(prefixed(code, Browser::Chrome, 54, 0), vec!["user-select"]);
+    assert_eq!(
+        prefixed(code, Browser::Edge, 78, 0),
+        vec!["-webkit-user-select", "user-select"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Safari, 99, 0),
+        vec!["-webkit-user-select", "user-select"]
+    );
+    assert_eq!(
+        prefixed(code, Browser::Firefox, 68, 0),
+        vec!["-moz-user-select", "user-select"]
+    );
+}
+
+#[test]
+fn test_prefix_5() {
+    // other properties pass through
+    assert_eq!(
+        prefixed("color: red;", Browser::Safari, 9, 0),
+        vec!["color"]
+    );
+
+    // nothing is prefixed without targets
+    assert_eq!(
+        transpile("user-select: none;", &BTreeMap::new()),
+        ".a{user-select:none;}"
+    );
+
+    // prefixes are added in nested rules too
+    let targets = BTreeMap::from([(Browser::Safari, Version::new(14, 0))]);
+    assert_eq!(
+        transpile("&:hover { user-select: none; }", &targets),
+        ".a:hover{-webkit-user-select:none;user-select:none;}"
+    );
+}
@@ -1,121 +1,239 @@
 use once_cell::sync::OnceCell;
-use serde::Deserialize;
+use proc_macro2::TokenStream;
+use quote::quote;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use yew_style_in_rs_build::{cargo_workspace, config_root, profile_dir, Config, Workspace};
 
-// cache workspace directory
-static WORKSPACE: OnceCell<PathBuf> = OnceCell::new();
+// cache workspace directories
+static WORKSPACE: OnceCell<Workspace> = OnceCell::new();
 
-// Get flag that this build is release build or not.
+// cache profile directory
+static PROFILE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+// cache configuration root
+static CONFIG_ROOT: OnceCell<PathBuf> = OnceCell::new();
+
+// cache configuration and its path, or its error message
+static CONFIG: OnceCell<Result<(Config, Option<PathBuf>), String>> = OnceCell::new();
+
+// Get the value of the rustc argument.
 //
-// I don't want the profile of the proc macro itself,
-// so I check the args starting with `debuginfo` instead of `#[cfg(debug_assertions)]`.
-pub fn is_release() -> bool {
-    let mut is_release = true;
+// eg)
+// - `--out-dir target/debug/deps`
+// - `--out-dir=target/debug/deps`
+// - `-C opt-level=3` for `opt-level`
+fn get_arg(name: &str) -> Option<String> {
+    let mut value = None;
     let mut args = env::args();
     while let Some(arg) = args.next() {
-        if arg.starts_with("debuginfo") {
-            is_release = false;
+        if arg == name {
+            value = args.next();
+        } else if let Some(v) = arg.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+            value = Some(v.to_string());
+        } else if arg == "-C" {
+            if let Some(codegen) = args.next() {
+                if let Some(v) = codegen.strip_prefix(name).and_then(|v| v.strip_prefix('=')) {
+                    value = Some(v.to_string());
+                }
+            }
         }
     }
-    is_release
+    value
+}
+
+// Get flag that this build is release build or not.
+//
+// I don't want the profile of the proc macro itself,
+// so I check the `opt-level` codegen option instead of `#[cfg(debug_assertions)]`.
+// Cargo passes it only if it is not 0.
+// `debuginfo` can be enabled in release builds, so it is not checked.
+pub fn is_release() -> bool {
+    get_arg("opt-level").is_some_and(|level| level != "0")
+}
+
+// Get flag that debug assertions are enabled in this build.
+//
+// Ids have readable names while debug assertions are enabled,
+// the same as `cfg!(debug_assertions)` in the expansion of `dyn css!` and in the runtime.
+// Cargo passes `-C debug-assertions` only if it differs from the default,
+// which is enabled for `opt-level=0`.
+pub fn debug_assertions() -> bool {
+    match get_arg("debug-assertions").as_deref() {
+        Some("on" | "yes" | "y" | "true") => true,
+        Some(_) => false,
+        None => !is_release(),
+    }
 }
 
-// Get output directory.
+// Get profile directory, where the CSS fragments are written.
 // eg)
 // - target/debug/
 // - target/release/
-// - workspace/target/release/
+// - workspace/target/wasm32-unknown-unknown/wasm-release/
 //
 // `OUT_DIR` env can't be used in proc macro.
 // https://github.com/rust-lang/cargo/issues/9084
 //
-// Get the directory from which the last directory was removed from the `--out-dir` argument
-// as a workaround.
-// if there is no `--out-dir` argument, then use default directory for `workspace/target/{profile}`.
+// The directory is found in this order.
+// - The profile directory of the `--out-dir` argument like `target/<triple>/<profile>/deps`,
+//   whatever the profile name, the target triple or the target directory is.
+// - `<target directory>/<triple>/{debug,release}` when there is no `--out-dir` argument,
+//   e.g. rustc is invoked by other tools than cargo.
+//
+// `YEW_STYLE_IN_RS_OUT_DIR` and `out-dir` of the configuration change only the directory of the css files,
+// so that the fragments and the lockfile of each profile are separated.
 //
 // This method create Cargo.lock file if not exists, so this method should not call when dry-run.
-pub fn get_out_dir() -> PathBuf {
-    let profile = if is_release() { "release" } else { "debug" };
-
-    let mut out_dir = get_cargo_workspace().join("target").join(&profile);
-
-    let mut args = env::args();
-    while let Some(arg) = args.next() {
-        if arg == "--out-dir" {
-            if let Some(dir) = args.next() {
-                out_dir = PathBuf::from(dir);
+pub fn get_profile_dir() -> PathBuf {
+    PROFILE_DIR
+        .get_or_init(|| {
+            if let Some(dir) = get_arg("--out-dir").and_then(|dir| profile_dir(Path::new(&dir))) {
+                return dir;
             }
-        }
-    }
-
-    while !out_dir.ends_with(&profile) {
-        if !out_dir.pop() {
-            panic!("Failed to find out_dir");
-        }
-    }
 
-    out_dir
+            let mut out_dir = get_cargo_workspace().target_directory.clone();
+            if let Some(triple) = get_arg("--target") {
+                out_dir.push(triple);
+            }
+            out_dir.push(if is_release() { "release" } else { "debug" });
+            out_dir
+        })
+        .clone()
 }
 
-// Get workspace directory path.
+// Get workspace directories.
 //
 // Currently rust workspace directory information is only in `cargo metadata`.
-// So execute `cargo metadata` and parse json to get workspace_root directory.
+// So execute `cargo metadata` and parse json to get the workspace root and target directories.
 //
 // This method create Cargo.lock file if not exists, so this method should not call when dry-run.
-pub fn get_cargo_workspace() -> PathBuf {
-    WORKSPACE
-        .get_or_init(|| {
-            let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
-            let output = std::process::Command::new(env::var("CARGO").unwrap())
-                .arg("metadata")
-                .arg("--format-version=1")
-                .current_dir(manifest_dir)
-                .output()
-                .unwrap();
-
-            #[derive(Deserialize)]
-            struct Metadata {
-                workspace_root: String,
-            }
+pub fn get_cargo_workspace() -> &'static Workspace {
+    WORKSPACE.get_or_init(|| {
+        let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+        cargo_workspace(manifest_dir).expect("Failed to get the workspace of the package")
+    })
+}
 
-            let metadata: Metadata = serde_json::from_slice(&output.stdout).unwrap();
+// Get the directory of the configuration.
+//
+// `YEW_STYLE_IN_RS_CONFIG_DIR` env if set, so that crates from the registry or git
+// read the configuration of the top-level workspace.
+// Otherwise the root of the workspace of the crate being compiled.
+//
+// This method create Cargo.lock file if not exists, so this method should not call when dry-run.
+pub fn get_config_root() -> &'static Path {
+    CONFIG_ROOT
+        .get_or_init(|| config_root(None).unwrap_or_else(|| get_cargo_workspace().root.clone()))
+}
 
-            let workspace = PathBuf::from(metadata.workspace_root);
-            workspace
-        })
-        .into()
+// Get the configuration and the path of the file it is read from.
+//
+// `yew-style.toml` or the metadata of `Cargo.toml` in the configuration root.
+// The error message is reported as a compile error by the macros.
+//
+// This method create Cargo.lock file if not exists, so this method should not call when dry-run.
+fn get_config_with_path() -> Result<&'static (Config, Option<PathBuf>), String> {
+    CONFIG
+        .get_or_init(|| Config::load_with_path(get_config_root()).map_err(|e| format!("{e:#}")))
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+// Get the configuration of the workspace.
+//
+// This method create Cargo.lock file if not exists, so this method should not call when dry-run.
+pub fn get_config() -> Result<&'static Config, String> {
+    get_config_with_path().map(|(config, _)| config)
 }
 
-// Get dependencies and itself package names.
+// Track the configuration file, so that cargo rebuilds the styled crates when it is edited.
 //
-// `cargo metadata` return dependencies information/
-// So execute `cargo metadata` and parse json to get dependencies name.
+// Proc macros can not tell cargo the files they read on stable Rust,
+// so expand `include_bytes!` of the file instead.
+// `Cargo.toml` is tracked only if the configuration is in its metadata.
+// A configuration file created after the build is not tracked.
 //
 // This method create Cargo.lock file if not exists, so this method should not call when dry-run.
-pub fn get_cargo_packages() -> Vec<String> {
-    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
-    let output = std::process::Command::new(env::var("CARGO").unwrap())
-        .arg("metadata")
-        .arg("--format-version=1")
-        .current_dir(manifest_dir)
-        .output()
-        .unwrap();
-
-    #[derive(Deserialize)]
-    struct Package {
-        name: String,
-    }
-    #[derive(Deserialize)]
-    struct Metadata {
-        packages: Vec<Package>,
+pub fn track_config() -> TokenStream {
+    match get_config_with_path() {
+        Ok((_, Some(path))) => {
+            let path = path.to_string_lossy();
+            quote!(
+                const _: &[u8] = include_bytes!(#path);
+            )
+        }
+        _ => TokenStream::new(),
     }
+}
+
+// Get the readable name of the declaration for ids in debug builds.
+// Characters not allowed in class names are replaced with `_`.
+//
+// eg)
+// - crate `my-component-a`, `let css = css! {...}` -> my_component_a__css
+// - crate `my-app`, `let size = variants! { size: Size { Size::Small => ... } }` -> my_app__size_Size_Small
+pub fn debug_name(name: &str) -> String {
+    let crate_name = env::var("CARGO_PKG_NAME").unwrap();
+    let sanitize = |s: &str| {
+        let mut sanitized = String::new();
+        for ch in s.chars() {
+            if ch.is_ascii_alphanumeric() {
+                sanitized.push(ch);
+            } else if !sanitized.ends_with('_') {
+                sanitized.push('_');
+            }
+        }
+        sanitized.trim_matches('_').to_string()
+    };
+    format!("{}__{}", sanitize(&crate_name), sanitize(name))
+}
+
+// Get the source file path of the span relative to the package root.
+// Empty if the compiler does not tell the source file of spans to proc macros, before Rust 1.88.
+//
+// eg)
+// src/components/button.rs
+pub fn source_file(span: proc_macro2::Span) -> PathBuf {
+    let file = match span.local_file() {
+        Some(file) => file,
+        None => return PathBuf::new(),
+    };
+    let file = if file.is_absolute() {
+        file
+    } else {
+        env::current_dir().unwrap().join(file)
+    };
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    file.strip_prefix(&manifest_dir)
+        .map(Path::to_path_buf)
+        .unwrap_or(file)
+}
 
-    let metadata: Metadata = serde_json::from_slice(&output.stdout).unwrap();
-    metadata
-        .packages
-        .into_iter()
-        .map(|p| p.name)
-        .collect::<Vec<_>>()
+// Get the module path of the source file of the span,
+// relative to the package root to be the same on every machine.
+//
+// eg)
+// - src/lib.rs -> ""
+// - src/components/button.rs -> "components::button"
+// - src/components/mod.rs -> "components"
+//
+// Inline `mod` blocks are not distinguished,
+// so the id seed also contains the declaration name and the content.
+pub fn module_path(span: proc_macro2::Span) -> String {
+    let mut segments = source_file(span)
+        .with_extension("")
+        .iter()
+        .map(|segment| segment.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    if segments.first().map(String::as_str) == Some("src") {
+        segments.remove(0);
+    }
+    if matches!(
+        segments.last().map(String::as_str),
+        Some("lib" | "main" | "mod")
+    ) {
+        segments.pop();
+    }
+    segments.join("::")
 }
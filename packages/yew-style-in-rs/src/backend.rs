@@ -0,0 +1,479 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CssStyleSheet, Document, Element, HtmlStyleElement, Node, NodeList, ShadowRoot};
+
+use crate::dyn_css::StyleId;
+use crate::error::StyleError;
+
+// Effects of `StyleManager` on the outside world.
+//
+// `StyleManager` keeps track of the ids and reference counts of styles,
+// and the backend mounts/unmounts the transpiled rules of each style.
+pub trait StyleBackend {
+    // Mount rules of the style.
+    fn mount(&mut self, style_id: &StyleId, rules: Vec<String>) -> Result<(), StyleError>;
+
+    // Unmount rules of the style.
+    fn unmount(&mut self, style_id: &StyleId) -> Result<(), StyleError>;
+
+    // Whether styles are registered while rendering instead of in effects.
+    // Server side rendering does not run effects.
+    fn register_on_render(&self) -> bool {
+        false
+    }
+
+    // Selector scoping the rules of the style.
+    fn scope_selector(&self, style_id: &StyleId) -> String {
+        format!(".{}", style_id.id())
+    }
+
+    // Styles already mounted outside of the manager with its style id and css code,
+    // e.g. rendered by the server.
+    // `StyleManager` adopts them at startup and reuses them for the same code.
+    fn adopt(&mut self) -> Result<Vec<(StyleId, String)>, StyleError> {
+        Ok(vec![])
+    }
+
+    // CSP nonce of the style elements,
+    // also used for style elements created outside of the backend, e.g. by the inspector.
+    fn nonce(&mut self) -> Result<Option<String>, StyleError> {
+        Ok(None)
+    }
+
+    // Errors of operations which succeeded as a whole, e.g. rules rejected by the browser.
+    // `StyleManager` takes them after each operation and reports them to the error hook.
+    fn take_errors(&mut self) -> Vec<StyleError> {
+        vec![]
+    }
+}
+
+// How `BrowserBackend` mounts dynamic styles into the document.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MountMode {
+    // Keep one shared `<style data-style-sheet>` element
+    // and insert/delete each rule with `insertRule` / `deleteRule`.
+    #[default]
+    Sheet,
+    // Create one `<style data-style="dynamic-XXXXXXXX">` element per style.
+    Element,
+    // Keep one constructable style sheet in `adoptedStyleSheets` of the root
+    // and insert/delete each rule with `insertRule` / `deleteRule`.
+    // No style element is created, so no CSP nonce is needed.
+    // Falls back to `MountMode::Sheet` if the browser does not support constructable style sheets.
+    Adopted,
+}
+
+// Where `BrowserBackend` mounts style elements.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum StyleRoot {
+    // The head of the document of the window.
+    #[default]
+    Head,
+    // The head of another document, e.g. the document of an iframe.
+    Document(Document),
+    // A shadow root, e.g. of the web component the app renders into.
+    // If `host` is true, rules are scoped with `:host(.dynamic-XXXXXXXX)`
+    // so that the styles apply to the shadow host with the class.
+    ShadowRoot {
+        root: ShadowRoot,
+        host: bool,
+    },
+    // A specific element.
+    Element(Element),
+}
+// Error of the failed DOM operation.
+fn dom_error(message: &str) -> StyleError {
+    StyleError::Dom(message.to_string())
+}
+
+impl StyleRoot {
+    // The node style elements are appended to.
+    fn container(&self) -> Result<Node, StyleError> {
+        match self {
+            Self::Head | Self::Document(_) => {
+                let document = self.document()?;
+                match document.head() {
+                    Some(head) => Ok(head.into()),
+                    None => document
+                        .document_element()
+                        .map(Into::into)
+                        .ok_or_else(|| dom_error("Failed to get document element")),
+                }
+            }
+            Self::ShadowRoot { root, .. } => Ok(root.clone().into()),
+            Self::Element(element) => Ok(element.clone().into()),
+        }
+    }
+
+    // The document creating style elements.
+    fn document(&self) -> Result<Document, StyleError> {
+        match self {
+            Self::Head => web_sys::window()
+                .and_then(|window| window.document())
+                .ok_or_else(|| dom_error("Failed to get document")),
+            Self::Document(document) => Ok(document.clone()),
+            Self::ShadowRoot { root, .. } => root
+                .owner_document()
+                .ok_or_else(|| dom_error("Failed to get owner document")),
+            Self::Element(element) => element
+                .owner_document()
+                .ok_or_else(|| dom_error("Failed to get owner document")),
+        }
+    }
+
+    // The object having `adoptedStyleSheets`.
+    fn adopted_style_sheets_owner(&self) -> Result<JsValue, StyleError> {
+        match self {
+            Self::ShadowRoot { root, .. } => Ok(root.clone().into()),
+            _ => Ok(self.document()?.into()),
+        }
+    }
+
+    fn query_selector(&self, selectors: &str) -> Result<Option<Element>, StyleError> {
+        match self {
+            Self::Head => self.document()?.query_selector(selectors),
+            Self::Document(document) => document.query_selector(selectors),
+            Self::ShadowRoot { root, .. } => root.query_selector(selectors),
+            Self::Element(element) => element.query_selector(selectors),
+        }
+        .map_err(|_| dom_error("Failed to query selector"))
+    }
+
+    fn query_selector_all(&self, selectors: &str) -> Result<NodeList, StyleError> {
+        match self {
+            Self::Head => self.document()?.query_selector_all(selectors),
+            Self::Document(document) => document.query_selector_all(selectors),
+            Self::ShadowRoot { root, .. } => root.query_selector_all(selectors),
+            Self::Element(element) => element.query_selector_all(selectors),
+        }
+        .map_err(|_| dom_error("Failed to query selector"))
+    }
+}
+
+// Backend mounting styles into the head of the document or another `StyleRoot`.
+pub struct BrowserBackend {
+    mode: MountMode,
+    root: StyleRoot,
+    // CSP nonce set to style elements.
+    // If not set, read from `<meta name="csp-nonce" content="...">` of the document.
+    nonce: Option<String>,
+    // The shared style sheet of `MountMode::Sheet` and `MountMode::Adopted`.
+    // Created lazily at the first mount.
+    sheet: Option<CssStyleSheet>,
+    // Style id of each rule in the shared style sheet.
+    // The index of this vec is the rule index in the style sheet,
+    // so the rule indices of a style are the positions of its style id.
+    sheet_rules: Vec<StyleId>,
+    // Style ids of the server rendered style elements adopted at startup.
+    // They are elements even in `MountMode::Sheet` and `MountMode::Adopted`.
+    adopted_elements: HashSet<StyleId>,
+    // Errors not returned by the operations, taken by `StyleManager`.
+    errors: Vec<StyleError>,
+}
+impl BrowserBackend {
+    pub fn new(mode: MountMode) -> Self {
+        Self::with_root(mode, StyleRoot::default())
+    }
+
+    pub fn with_root(mode: MountMode, root: StyleRoot) -> Self {
+        Self {
+            mode,
+            root,
+            nonce: None,
+            sheet: None,
+            sheet_rules: vec![],
+            adopted_elements: HashSet::new(),
+            errors: vec![],
+        }
+    }
+
+    pub fn mount_mode(&self) -> MountMode {
+        self.mode
+    }
+
+    pub fn root(&self) -> &StyleRoot {
+        &self.root
+    }
+
+    // Set CSP nonce to style elements created after this call.
+    pub fn set_nonce(&mut self, nonce: impl Into<String>) {
+        self.nonce = Some(nonce.into());
+    }
+
+    // Nonce set by `set_nonce`, or read from `<meta name="csp-nonce" content="...">`.
+    fn read_nonce(&mut self) -> Result<Option<String>, StyleError> {
+        if self.nonce.is_none() {
+            self.nonce = self
+                .root
+                .document()?
+                .query_selector("meta[name=csp-nonce]")
+                .map_err(|_| dom_error("Failed to query selector"))?
+                .and_then(|meta| meta.get_attribute("content"));
+        }
+        Ok(self.nonce.clone())
+    }
+
+    // Create a style element with the nonce.
+    fn create_style_element(&mut self) -> Result<Element, StyleError> {
+        let style_element = self
+            .root
+            .document()?
+            .create_element("style")
+            .map_err(|_| dom_error("Failed to create style element"))?;
+        if let Some(nonce) = self.read_nonce()? {
+            style_element
+                .set_attribute("nonce", &nonce)
+                .map_err(|_| dom_error("Failed to set nonce attribute"))?;
+        }
+        Ok(style_element)
+    }
+
+    // Create a constructable style sheet and push it to `adoptedStyleSheets` of the root.
+    fn adopted_sheet(&self) -> Option<CssStyleSheet> {
+        let owner = self.root.adopted_style_sheets_owner().ok()?;
+        let sheets = js_sys::Reflect::get(&owner, &JsValue::from_str("adoptedStyleSheets")).ok()?;
+        if !js_sys::Array::is_array(&sheets) {
+            return None;
+        }
+        let sheet = CssStyleSheet::new().ok()?;
+
+        // `adoptedStyleSheets` is replaced with a new array
+        // because some browsers do not observe in place mutation.
+        let sheets = js_sys::Array::from(&sheets);
+        sheets.push(&sheet);
+        js_sys::Reflect::set(&owner, &JsValue::from_str("adoptedStyleSheets"), &sheets).ok()?;
+        Some(sheet)
+    }
+
+    // Get the shared style sheet, creating it at the first call.
+    // If the browser does not support constructable style sheets,
+    // fall back to `MountMode::Sheet`.
+    // If the browser does not expose CSSOM for the style element,
+    // fall back to `MountMode::Element`.
+    fn sheet(&mut self) -> Result<Option<CssStyleSheet>, StyleError> {
+        if self.sheet.is_none() && self.mode == MountMode::Adopted {
+            match self.adopted_sheet() {
+                Some(sheet) => self.sheet = Some(sheet),
+                None => self.mode = MountMode::Sheet,
+            }
+        }
+        if self.sheet.is_none() {
+            let container = self.root.container()?;
+            let style_element = self.create_style_element()?;
+            style_element
+                .set_attribute("data-style-sheet", "")
+                .map_err(|_| dom_error("Failed to set style attribute"))?;
+            container
+                .append_child(&style_element)
+                .map_err(|_| dom_error("Failed to mount style element"))?;
+
+            let sheet = style_element
+                .clone()
+                .dyn_into::<HtmlStyleElement>()
+                .ok()
+                .and_then(|style_element| style_element.sheet())
+                .and_then(|sheet| sheet.dyn_into::<CssStyleSheet>().ok());
+            match sheet {
+                Some(sheet) => self.sheet = Some(sheet),
+                None => {
+                    // Fall back to an element per style without leaving the empty element.
+                    container
+                        .remove_child(&style_element)
+                        .map_err(|_| dom_error("Failed to remove style"))?;
+                    self.mode = MountMode::Element;
+                }
+            }
+        }
+        Ok(self.sheet.clone())
+    }
+}
+impl Default for BrowserBackend {
+    fn default() -> Self {
+        Self::new(MountMode::default())
+    }
+}
+impl StyleBackend for BrowserBackend {
+    fn mount(&mut self, style_id: &StyleId, rules: Vec<String>) -> Result<(), StyleError> {
+        if self.mode != MountMode::Element {
+            if let Some(sheet) = self.sheet()? {
+                for rule in rules {
+                    let index = self.sheet_rules.len() as u32;
+                    // The browser rejects rules it can not parse.
+                    // Skip them as the browser does for invalid rules in a style element,
+                    // and report them to the error hook.
+                    match sheet.insert_rule_with_index(&rule, index) {
+                        Ok(_) => self.sheet_rules.push(style_id.clone()),
+                        Err(_) => self
+                            .errors
+                            .push(dom_error(&format!("Failed to insert rule: {rule}"))),
+                    }
+                }
+                return Ok(());
+            }
+        }
+
+        let container = self.root.container()?;
+        let style_element = self.create_style_element()?;
+        style_element
+            .set_attribute("data-style", style_id.id())
+            .map_err(|_| dom_error("Failed to set style attribute"))?;
+        style_element.set_text_content(Some(&rules.join("")));
+        container
+            .append_child(&style_element)
+            .map_err(|_| dom_error("Failed to mount style element"))?;
+        Ok(())
+    }
+
+    fn unmount(&mut self, style_id: &StyleId) -> Result<(), StyleError> {
+        // Styles mounted into the shared style sheet have no element to query.
+        let adopted_element = self.adopted_elements.remove(style_id);
+        if self.mode != MountMode::Element && !adopted_element {
+            if let Some(sheet) = self.sheet.clone() {
+                // Delete from the last rule so that the remaining indices are not shifted.
+                for index in (0..self.sheet_rules.len()).rev() {
+                    if &self.sheet_rules[index] == style_id {
+                        sheet
+                            .delete_rule(index as u32)
+                            .map_err(|_| dom_error("Failed to delete rule"))?;
+                        self.sheet_rules.remove(index);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(style) = self
+            .root
+            .query_selector(&format!("style[data-style={}]", style_id.id()))?
+        {
+            if let Some(parent) = style.parent_node() {
+                parent
+                    .remove_child(&style)
+                    .map_err(|_| dom_error("Failed to remove style"))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn scope_selector(&self, style_id: &StyleId) -> String {
+        match self.root {
+            StyleRoot::ShadowRoot { host: true, .. } => format!(":host(.{})", style_id.id()),
+            _ => format!(".{}", style_id.id()),
+        }
+    }
+
+    // Adopt `<style data-style="...">` elements rendered by the server.
+    fn adopt(&mut self) -> Result<Vec<(StyleId, String)>, StyleError> {
+        let styles = self.root.query_selector_all("style[data-style]")?;
+
+        let mut adopted = vec![];
+        for index in 0..styles.length() {
+            if let Some(style) = styles
+                .get(index)
+                .and_then(|node| node.dyn_into::<Element>().ok())
+            {
+                if let Some(id) = style.get_attribute("data-style") {
+                    let code = style.text_content().unwrap_or_default();
+                    self.adopted_elements.insert(StyleId::new(&id));
+                    adopted.push((StyleId::new(&id), code));
+                }
+            }
+        }
+        Ok(adopted)
+    }
+
+    fn nonce(&mut self) -> Result<Option<String>, StyleError> {
+        self.read_nonce()
+    }
+
+    fn take_errors(&mut self) -> Vec<StyleError> {
+        std::mem::take(&mut self.errors)
+    }
+}
+
+// Backend recording styles in memory.
+//
+// Used for server side rendering.
+// The recorded styles are rendered as `<style>` tags for the head of the html.
+// Clones share the same records, so keep a clone to read the styles after rendering.
+#[derive(Clone, Default)]
+pub struct CollectBackend {
+    styles: Rc<RefCell<Vec<(StyleId, String)>>>,
+}
+// Escape `</` in the css so that the code cannot close the style tag.
+// The adopted text of the server rendered style is in this form.
+pub(crate) fn escape_style_text(code: &str) -> String {
+    code.replace("</", "<\\/")
+}
+
+impl CollectBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Currently mounted styles with its style id and css code in mount order.
+    pub fn styles(&self) -> Vec<(StyleId, String)> {
+        self.styles.borrow().clone()
+    }
+
+    // Render mounted styles as `<style data-style="...">` tags.
+    //
+    // `</` in css code is escaped not to close the style tag.
+    pub fn to_style_tags(&self) -> String {
+        self.style_tags("")
+    }
+
+    // Render mounted styles as `<style data-style="..." nonce="...">` tags
+    // for the pages served with CSP nonce.
+    pub fn to_style_tags_with_nonce(&self, nonce: &str) -> String {
+        let nonce = nonce
+            .replace('&', "&amp;")
+            .replace('"', "&quot;")
+            .replace('<', "&lt;");
+        self.style_tags(&format!(r#" nonce="{nonce}""#))
+    }
+
+    fn style_tags(&self, attributes: &str) -> String {
+        self.styles
+            .borrow()
+            .iter()
+            .map(|(style_id, code)| {
+                format!(
+                    r#"<style data-style="{}"{}>{}</style>"#,
+                    style_id.id(),
+                    attributes,
+                    escape_style_text(code)
+                )
+            })
+            .collect()
+    }
+
+    // Forget all recorded styles.
+    pub fn clear(&self) {
+        self.styles.borrow_mut().clear();
+    }
+}
+impl StyleBackend for CollectBackend {
+    fn mount(&mut self, style_id: &StyleId, rules: Vec<String>) -> Result<(), StyleError> {
+        self.styles
+            .borrow_mut()
+            .push((style_id.clone(), rules.join("")));
+        Ok(())
+    }
+
+    fn unmount(&mut self, style_id: &StyleId) -> Result<(), StyleError> {
+        self.styles.borrow_mut().retain(|(id, _)| id != style_id);
+        Ok(())
+    }
+
+    fn register_on_render(&self) -> bool {
+        true
+    }
+
+    // Styles recorded before the manager starts are adopted.
+    fn adopt(&mut self) -> Result<Vec<(StyleId, String)>, StyleError> {
+        Ok(self.styles())
+    }
+}
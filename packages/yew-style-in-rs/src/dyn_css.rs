@@ -1,6 +1,12 @@
+use instant::Instant;
+use std::collections::HashMap;
 use yew::prelude::*;
+use yew_style_in_rs_core::ast::RuntimeCss;
 
-#[derive(Clone, PartialEq, Eq, Hash)]
+use crate::cursor::Cursor;
+use crate::error::{report_error, StyleError};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct StyleId(String);
 impl StyleId {
     pub fn new(id: &str) -> Self {
@@ -17,11 +23,19 @@ impl Into<Classes> for StyleId {
     }
 }
 
-#[derive(Clone, PartialEq)]
+// Style registered in `StyleManager`.
+//
+// `StyleManager` keeps one content per code with the reference count,
+// and `register` returns a clone of it with the handle of the registration.
+#[derive(Clone, Debug, PartialEq)]
 pub struct StyleContent {
     style_id: StyleId,
     code: String,
     count: usize,
+    handle: u64,
+    created_at: Instant,
+    // Size of the mounted css code.
+    bytes: usize,
 }
 impl StyleContent {
     pub fn new(style_id: StyleId, code: String) -> Self {
@@ -29,9 +43,27 @@ impl StyleContent {
             style_id,
             code,
             count: 0,
+            handle: 0,
+            created_at: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    pub(crate) fn with_bytes(self, bytes: usize) -> Self {
+        Self { bytes, ..self }
+    }
+
+    pub(crate) fn with_handle(&self, handle: u64) -> Self {
+        Self {
+            handle,
+            ..self.clone()
         }
     }
 
+    pub(crate) fn handle(&self) -> u64 {
+        self.handle
+    }
+
     pub fn style_id(&self) -> StyleId {
         self.style_id.clone()
     }
@@ -44,8 +76,89 @@ impl StyleContent {
         self.count += 1;
     }
 
+    // Returns true when no reference remains.
+    // Never underflows even if decremented more than incremented.
     pub fn decrement(&mut self) -> bool {
-        self.count -= 1;
+        self.count = self.count.saturating_sub(1);
         self.count == 0
     }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn created_at(&self) -> Instant {
+        self.created_at
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+}
+
+// Replace `##<animation_name>##` in the code of `dyn css!`.
+//
+// Names declared in `keyframes!` are replaced at compile time.
+// Names declared in `dyn keyframes!` are replaced with `<animation_name>-<dynamic id>`
+// using `dyn_names_map` which maps dynamic ids to declared animation names.
+pub fn resolve_animation_names(
+    code: &str,
+    animation_names: &[String],
+    dyn_names_map: &HashMap<String, Vec<String>>,
+) -> String {
+    let mut cursor = Cursor::new(code);
+    let mut code = String::new();
+    while !cursor.is_empty() {
+        if cursor.peek('#') {
+            cursor.take('#');
+            if cursor.peek('#') {
+                cursor.take('#');
+                let name = match cursor.take_until('#') {
+                    Ok(name) => name,
+                    Err(_) => break,
+                };
+
+                if let Some(name) = animation_names.iter().find(|&n| n == &name) {
+                    code += name;
+                } else if let Some((id, _)) = dyn_names_map.iter().find(|(_, n)| n.contains(&name))
+                {
+                    code += &name;
+                    code += "-";
+                    code += id;
+                } else {
+                    code += "##";
+                    code += &name;
+                    code += "##";
+                }
+
+                cursor.take('#');
+                if cursor.peek('#') {
+                    cursor.take('#');
+                }
+            } else {
+                code.push('#');
+            }
+        } else if let Some(ch) = cursor.next() {
+            code.push(ch);
+        }
+    }
+    code
+}
+
+// Report the parse error of the code of `dyn css!` with the source location.
+// Called by `dyn css!` expansion in debug builds.
+//
+// With the `strict` feature, panic instead, e.g. to fail tests.
+pub fn check_dyn_css(code: &str, location: &str) {
+    if let Err((_, message)) = RuntimeCss::parse(code) {
+        let error = StyleError::InvalidCss {
+            message,
+            code: code.to_string(),
+            location: location.to_string(),
+        };
+        if cfg!(feature = "strict") {
+            panic!("yew-style-in-rs: {error}");
+        }
+        report_error(&error);
+    }
 }
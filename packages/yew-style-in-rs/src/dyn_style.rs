@@ -0,0 +1,140 @@
+//! `DynStyle` handle registering runtime styles for struct components.
+//!
+//! `style!` expands to hooks, so it can only be used in function components.
+//! `DynStyle` registers the style on creation, re-registers it on `update`
+//! and unregisters it on drop.
+
+use std::panic::Location;
+use yew::prelude::*;
+
+use crate::dyn_css::{check_dyn_css, StyleContent, StyleId};
+use crate::hooks::DynStyleKind;
+use crate::runtime_manager::StyleManager;
+
+/// Handle of a runtime style registered while it is alive.
+///
+/// The code is transpiled in the same way as `dyn css!`.
+/// `dyn_style!` creates it from the same syntax as `dyn css!`.
+///
+/// ```rust,ignore
+/// use yew::prelude::*;
+/// use yew_style_in_rs::dyn_style::DynStyle;
+/// use yew_style_in_rs::dyn_style;
+///
+/// pub struct Bar {
+///     width: u32,
+///     style: DynStyle,
+/// }
+/// impl Component for Bar {
+///     type Message = u32;
+///     type Properties = ();
+///
+///     fn create(_ctx: &Context<Self>) -> Self {
+///         let width = 10;
+///         Self {
+///             width,
+///             style: dyn_style!(r#"width: ${width}px; & > span { color: red; }"#),
+///         }
+///     }
+///
+///     fn update(&mut self, _ctx: &Context<Self>, width: u32) -> bool {
+///         self.width = width;
+///         dyn_style!(self.style, r#"width: ${width}px; & > span { color: red; }"#)
+///     }
+///
+///     fn view(&self, _ctx: &Context<Self>) -> Html {
+///         html! { <div class={&self.style}><span>{"bar"}</span></div> }
+///     }
+/// }
+/// ```
+pub struct DynStyle {
+    manager: StyleManager,
+    code: String,
+    style: StyleContent,
+    // readable name of the id in debug builds, `None` for `dynamic-<hash>`
+    name: Option<&'static str>,
+}
+impl DynStyle {
+    /// Register the code with the singleton `StyleManager`.
+    ///
+    /// The id is `dynamic-<hash>` in every build,
+    /// because there is no declaration to name it after.
+    #[track_caller]
+    pub fn new(code: String) -> Self {
+        Self::with_manager(StyleManager::default(), code)
+    }
+
+    /// Register the code with the manager,
+    /// e.g. the one provided with `ContextProvider<StyleManager>`.
+    #[track_caller]
+    pub fn with_manager(manager: StyleManager, code: String) -> Self {
+        Self::register(manager, code, None, Location::caller())
+    }
+
+    // Register with the readable name of the `dyn_style!` expansion.
+    #[doc(hidden)]
+    #[track_caller]
+    pub fn with_name(code: String, name: Option<&'static str>) -> Self {
+        Self::register(StyleManager::default(), code, name, Location::caller())
+    }
+
+    fn register(
+        manager: StyleManager,
+        code: String,
+        name: Option<&'static str>,
+        caller: &Location,
+    ) -> Self {
+        check(&code, caller);
+        let style = manager.register_named(code.clone(), DynStyleKind::Css, name);
+        Self {
+            manager,
+            code,
+            style,
+            name,
+        }
+    }
+
+    /// Re-register with the new code.
+    ///
+    /// Returns true if the code is changed, so that it can be returned from `Component::update`.
+    #[track_caller]
+    pub fn update(&mut self, code: String) -> bool {
+        if code == self.code {
+            return false;
+        }
+        check(&code, Location::caller());
+        let style = self
+            .manager
+            .register_named(code.clone(), DynStyleKind::Css, self.name);
+        let prev_style = std::mem::replace(&mut self.style, style);
+        self.manager.unregister(prev_style);
+        self.code = code;
+        true
+    }
+
+    pub fn style_id(&self) -> StyleId {
+        self.style.style_id()
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+}
+impl Drop for DynStyle {
+    fn drop(&mut self) {
+        self.manager.unregister(self.style.clone());
+    }
+}
+impl From<&DynStyle> for Classes {
+    fn from(style: &DynStyle) -> Self {
+        style.style_id().into()
+    }
+}
+
+// Report parse errors of the code with the location of the caller in debug builds.
+fn check(code: &str, caller: &Location) {
+    if cfg!(debug_assertions) {
+        let location = format!("{}:{}:{}", caller.file(), caller.line(), caller.column());
+        check_dyn_css(code, &location);
+    }
+}
@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::dyn_css::StyleId;
+
+// Error of `StyleManager` and `StyleBackend`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StyleError {
+    // A DOM operation failed, e.g. the document is detached.
+    Dom(String),
+    // The code of `dyn keyframes!` is malformed.
+    InvalidKeyframes(String),
+    // The style is not registered or already unregistered.
+    NotRegistered(StyleId),
+    // The style is not mounted because the number of mounted styles reaches `max_live_styles`.
+    // It is mounted when other styles are unmounted.
+    LimitReached(StyleId),
+    // The code of `dyn css!` has a parse error.
+    // The declarations before the error are still applied.
+    InvalidCss {
+        message: String,
+        code: String,
+        // `file:line:column` of the `dyn css!` declaration.
+        location: String,
+    },
+}
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Dom(message) => write!(f, "DOM operation failed: {message}"),
+            Self::InvalidKeyframes(message) => write!(f, "invalid dyn keyframes: {message}"),
+            Self::NotRegistered(style_id) => write!(f, "{} is not registered", style_id.id()),
+            Self::LimitReached(style_id) => write!(
+                f,
+                "{} is not mounted until other styles are unmounted because the number of dynamic styles reaches `max_live_styles`",
+                style_id.id()
+            ),
+            Self::InvalidCss {
+                message,
+                code,
+                location,
+            } => write!(f, "invalid dyn css at {location}: {message}\n{code}"),
+        }
+    }
+}
+impl std::error::Error for StyleError {}
+
+type ErrorHook = Rc<dyn Fn(&StyleError)>;
+
+thread_local! {
+    static ERROR_HOOK: RefCell<Option<ErrorHook>> = RefCell::new(None);
+}
+
+// Set the hook called with errors `StyleManager` can not return,
+// e.g. errors in the effects of `dyn css!` and `dyn keyframes!`.
+//
+// By default, errors are logged to the console.
+pub fn set_error_hook(hook: impl Fn(&StyleError) + 'static) {
+    ERROR_HOOK.with(|h| *h.borrow_mut() = Some(Rc::new(hook)));
+}
+
+// Restore the default hook logging errors to the console.
+pub fn reset_error_hook() {
+    ERROR_HOOK.with(|h| *h.borrow_mut() = None);
+}
+
+// Report the error to the hook.
+pub fn report_error(error: &StyleError) {
+    // Clone the hook so that the hook can set another hook.
+    let hook = ERROR_HOOK.with(|h| h.borrow().clone());
+    match hook {
+        Some(hook) => hook(error),
+        None => {
+            #[cfg(target_arch = "wasm32")]
+            gloo::console::error!(format!("yew-style-in-rs: {error}"));
+            #[cfg(not(target_arch = "wasm32"))]
+            eprintln!("yew-style-in-rs: {error}");
+        }
+    }
+}
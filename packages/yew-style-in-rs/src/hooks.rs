@@ -0,0 +1,176 @@
+// Hooks registering runtime styles for css code built programmatically,
+// e.g. from a CMS or a chart config.
+//
+// The code is transpiled in the same way as `dyn css!` and `dyn keyframes!`,
+// and the style is unregistered when the code changes or the component is destroyed.
+
+use std::panic::Location;
+use yew::prelude::*;
+
+use crate::cursor::Cursor;
+use crate::dyn_css::{check_dyn_css, StyleContent, StyleId};
+use crate::error::report_error;
+use crate::runtime_manager::StyleManager;
+
+// Register the css code scoped with the returned class.
+//
+// Nesting with `&` is transpiled as in `dyn css!`.
+// In debug builds, parse errors are reported with the location of the caller.
+// The id is `dynamic-<hash>` in every build, because there is no declaration to name it after.
+#[track_caller]
+pub fn use_dyn_css(code: String) -> StyleId {
+    let caller = Location::caller();
+    let location = cfg!(debug_assertions)
+        .then(|| format!("{}:{}:{}", caller.file(), caller.line(), caller.column()));
+    use_dyn_style(code, DynStyleKind::Css, location, None)
+}
+
+// Register `@keyframes` blocks of the code
+// and return the style id with the animation names scoped with it.
+//
+// The names are in the order of the declarations.
+// The id is `dynamic-<hash>` in every build as `use_dyn_css`.
+pub fn use_dyn_keyframes(code: String) -> (StyleId, Vec<String>) {
+    let names = keyframes_names(&code);
+    let style_id = use_dyn_style(code, DynStyleKind::Keyframes, None, None);
+    let names = names
+        .into_iter()
+        .map(|name| {
+            if style_id.id().is_empty() {
+                name
+            } else {
+                format!("{}-{}", name, style_id.id())
+            }
+        })
+        .collect();
+    (style_id, names)
+}
+
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynStyleKind {
+    Css,
+    Keyframes,
+}
+
+// Register the runtime style of the code while the component is alive.
+// Used by `dyn css!` and `dyn keyframes!` expansion and the public hooks.
+//
+// `location` is the source location to report parse errors of `DynStyleKind::Css` code.
+// No check if `None`.
+// `name` is the readable name of the declaration for the id in debug builds,
+// like `my_crate__dyn_css` for `my_crate__dyn_css__AbCdEfGh`.
+#[doc(hidden)]
+pub fn use_dyn_style(
+    code: String,
+    kind: DynStyleKind,
+    location: Option<String>,
+    name: Option<&'static str>,
+) -> StyleId {
+    let prev_style_handle = use_mut_ref(|| None::<StyleContent>);
+    let style_state = use_state_eq(|| None::<StyleContent>);
+
+    // Server side rendering does not run effects, so register the style while rendering.
+    // Otherwise use the already mounted style until the effect registers the style,
+    // so that hydration does not flash unstyled content.
+    // Use the manager provided by the context if any, otherwise the singleton.
+    //
+    // The style registered while rendering is held by the handle of the component instance,
+    // so it is registered once and not again on every render until the code is changed.
+    // It is unregistered with the handle when the code is changed or the component is destroyed.
+    // On the server effects are not run, and the registrations live until the scope of
+    // `StyleManager::with_backend` or `scope_backend` for the rendering ends.
+    let manager = use_context::<StyleManager>().unwrap_or_default();
+    let rendered_style_id = if manager.register_on_render() {
+        let mut prev_style = prev_style_handle.borrow_mut();
+        match &*prev_style {
+            Some(style) if style.code() == code => Some(style.style_id()),
+            _ => {
+                check(&code, kind, &location);
+                let style = manager.register_named(code.clone(), kind, name);
+                let style_id = style.style_id();
+                if let Some(prev_style) = prev_style.replace(style) {
+                    manager.unregister(prev_style);
+                }
+                Some(style_id)
+            }
+        }
+    } else {
+        manager.mounted_style_id_named(&code, kind, name)
+    };
+
+    // Unregister previous style and register new style when code is changed.
+    // If registration fails, log the error and keep the previous style.
+    use_effect_with_deps(
+        {
+            let style_state = style_state.clone();
+            let prev_style_handle = prev_style_handle.clone();
+            let manager = manager.clone();
+            move |code: &String| {
+                // Skip the style already registered while rendering.
+                let registered = matches!(
+                    &*prev_style_handle.borrow(),
+                    Some(style) if style.code() == *code
+                );
+                if !registered {
+                    check(code, kind, &location);
+                    match manager.try_register_named(code.clone(), kind, name) {
+                        Ok(style) => {
+                            let prev_style = prev_style_handle.borrow_mut().replace(style.clone());
+                            if let Some(prev_style) = prev_style {
+                                manager.unregister(prev_style);
+                            }
+                            style_state.set(Some(style));
+                        }
+                        Err(error) => report_error(&error),
+                    }
+                }
+                || ()
+            }
+        },
+        code,
+    );
+
+    // Unregister style when destroy elements.
+    // Read the handle instead of the state captured at the first render.
+    use_effect_with_deps(
+        move |_| {
+            move || {
+                let style = prev_style_handle.borrow_mut().take();
+                if let Some(style) = style {
+                    manager.unregister(style);
+                }
+            }
+        },
+        (),
+    );
+
+    // return `dyn_css::StyleId` of current style.
+    // If no style, return empty `StyleId`
+    rendered_style_id
+        .or_else(|| (*style_state).as_ref().map(|style| style.style_id()))
+        .unwrap_or_else(|| StyleId::new(""))
+}
+
+fn check(code: &str, kind: DynStyleKind, location: &Option<String>) {
+    if let (DynStyleKind::Css, Some(location)) = (kind, location) {
+        check_dyn_css(code, location);
+    }
+}
+
+// Animation names declared by `@keyframes <name> {` in the code.
+pub(crate) fn keyframes_names(code: &str) -> Vec<String> {
+    let mut cursor = Cursor::new(code);
+    let mut names = vec![];
+    while cursor.take_until('@').is_ok() {
+        cursor.take('@');
+        match cursor.take_until_whitespace() {
+            Some(at_keyword) if at_keyword.ends_with("keyframes") => (),
+            _ => continue,
+        }
+        if let Ok(name) = cursor.take_until('{') {
+            names.push(name.trim().to_string());
+        }
+    }
+    names
+}
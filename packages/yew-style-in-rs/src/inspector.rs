@@ -0,0 +1,180 @@
+//! `<StyleInspector/>` component listing the dynamic styles managed by `StyleManager`.
+//!
+//! Enabled by the `inspector` feature. Intended for development builds only.
+
+use gloo::timers::callback::Interval;
+use yew::prelude::*;
+
+use crate::dyn_css::StyleId;
+use crate::runtime_manager::{StyleManager, StyleSnapshot};
+
+/// Props of [`StyleInspector`].
+#[derive(Properties, PartialEq)]
+pub struct StyleInspectorProps {
+    /// Refresh interval of the list in milliseconds.
+    #[prop_or(1000)]
+    pub interval: u32,
+}
+
+// Styles of the inspector.
+const INSPECTOR_CSS: &str = "\
+.yew-style-inspector { position: fixed; right: 8px; bottom: 8px; z-index: 2147483647; max-width: 60vw; max-height: 50vh; overflow: auto; background: #fff; color: #000; border: 1px solid #888; font: 12px monospace; }
+.yew-style-inspector-header { padding: 4px; cursor: pointer; background: #eee; }
+.yew-style-inspector table { border-collapse: collapse; }
+.yew-style-inspector tr { cursor: pointer; border-top: 1px solid #ddd; }
+.yew-style-inspector tr.selected { background: #fdf; }
+.yew-style-inspector td { padding: 2px 4px; }
+.yew-style-inspector td.number { text-align: right; }
+.yew-style-inspector td.code { white-space: pre; }
+";
+
+#[doc(hidden)]
+pub enum StyleInspectorMsg {
+    Refresh,
+    Select(StyleId),
+    Toggle,
+}
+
+/// Overlay listing the live dynamic styles with id, reference count, size, age and code.
+///
+/// Click a row to highlight the elements using the style.
+/// Uses the `StyleManager` provided with `ContextProvider` if any, otherwise the singleton.
+///
+/// ```rust,ignore
+/// html! {
+///     <>
+///         <App />
+///         <StyleInspector />
+///     </>
+/// }
+/// ```
+pub struct StyleInspector {
+    manager: StyleManager,
+    // CSP nonce of the style element of the inspector.
+    nonce: Option<String>,
+    snapshot: Vec<StyleSnapshot>,
+    selected: Option<StyleId>,
+    open: bool,
+    _interval: Interval,
+}
+impl Component for StyleInspector {
+    type Message = StyleInspectorMsg;
+    type Properties = StyleInspectorProps;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let manager = ctx
+            .link()
+            .context::<StyleManager>(Callback::noop())
+            .map(|(manager, _)| manager)
+            .unwrap_or_default();
+        let link = ctx.link().clone();
+        let interval = Interval::new(ctx.props().interval, move || {
+            link.send_message(StyleInspectorMsg::Refresh)
+        });
+        Self {
+            snapshot: manager.snapshot(),
+            nonce: manager.nonce(),
+            manager,
+            selected: None,
+            open: true,
+            _interval: interval,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            StyleInspectorMsg::Refresh => {
+                // Render every refresh while open to update the ages.
+                let snapshot = self.manager.snapshot();
+                if snapshot == self.snapshot && !self.open {
+                    return false;
+                }
+                self.snapshot = snapshot;
+            }
+            StyleInspectorMsg::Select(style_id) => {
+                if self.selected.as_ref() == Some(&style_id) {
+                    self.selected = None;
+                } else {
+                    self.selected = Some(style_id);
+                }
+            }
+            StyleInspectorMsg::Toggle => self.open = !self.open,
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let total_bytes: usize = self.snapshot.iter().map(|style| style.bytes).sum();
+        let header = format!(
+            "yew-style-in-rs: {} styles, {} bytes",
+            self.snapshot.len(),
+            total_bytes
+        );
+
+        // Styles are in the style element with the nonce instead of style attributes,
+        // which are blocked by CSP without `unsafe-inline`.
+        let highlight = match &self.selected {
+            Some(style_id) => format!(
+                ".{} {{ outline: 2px solid magenta !important; outline-offset: -2px; }}",
+                style_id.id()
+            ),
+            None => String::new(),
+        };
+
+        let rows = if self.open {
+            self.snapshot
+                .iter()
+                .map(|style| self.view_row(ctx, style))
+                .collect::<Html>()
+        } else {
+            html! {}
+        };
+
+        html! {
+            <div class="yew-style-inspector">
+                <style nonce={self.nonce.clone()}>
+                    { INSPECTOR_CSS }
+                    { highlight }
+                </style>
+                <div class="yew-style-inspector-header" onclick={ctx.link().callback(|_| StyleInspectorMsg::Toggle)}>
+                    { header }
+                </div>
+                <table>
+                    { rows }
+                </table>
+            </div>
+        }
+    }
+}
+impl StyleInspector {
+    fn view_row(&self, ctx: &Context<Self>, style: &StyleSnapshot) -> Html {
+        let style_id = style.style_id.clone();
+        let onclick = ctx
+            .link()
+            .callback(move |_| StyleInspectorMsg::Select(style_id.clone()));
+        let selected = (self.selected.as_ref() == Some(&style.style_id)).then_some("selected");
+        let code = if style.code.chars().count() > 60 {
+            style.code.chars().take(60).collect::<String>() + "…"
+        } else {
+            style.code.clone()
+        };
+        let state = if !style.mounted {
+            "unmounted"
+        } else if style.ref_count == 0 {
+            "released"
+        } else {
+            ""
+        };
+
+        html! {
+            <tr class={classes!(selected)} {onclick}>
+                <td>{ style.style_id.id() }</td>
+                <td class="number">{ style.ref_count }</td>
+                <td class="number">{ format!("{}B", style.bytes) }</td>
+                <td class="number">{ format!("{}s", style.created_at.elapsed().as_secs()) }</td>
+                <td>{ state }</td>
+                <td class="code" title={style.code.clone()}>{ code }</td>
+            </tr>
+        }
+    }
+}
@@ -1,21 +1,38 @@
 #![doc = include_str!("../README.md")]
 
+pub use yew_style_in_rs_macro::dyn_style;
 #[cfg(not(feature = "dry-run"))]
 pub use yew_style_in_rs_macro::style_with_write as style;
 #[cfg(feature = "dry-run")]
 pub use yew_style_in_rs_macro::style_without_write as style;
+pub use yew_style_in_rs_macro::styled;
 
 #[doc(hidden)]
 pub use yew_style_in_rs_core::*;
 
+pub mod units;
+
+pub mod hooks;
+
+pub mod dyn_style;
+
+#[cfg(feature = "inspector")]
+pub mod inspector;
+
 #[doc(hidden)]
 pub mod css;
 
 #[doc(hidden)]
 pub mod dyn_css;
 
-#[doc(hidden)]
 pub mod runtime_manager;
 
+pub mod backend;
+
+pub mod error;
+
 #[doc(hidden)]
 pub mod cursor;
+
+#[cfg(test)]
+mod tests;
@@ -1,161 +1,688 @@
+use instant::{Duration, Instant};
 use once_cell::unsync::Lazy;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::Rc;
-use std::{cell::RefCell, iter::repeat_with};
+use std::sync::atomic::{AtomicU64, Ordering};
 use yew_style_in_rs_core::ast::RuntimeCss;
+use yew_style_in_rs_core::id::hashed_id;
 use yew_style_in_rs_core::transpiler::TranspiledCss;
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::backend::CollectBackend;
+use crate::backend::{escape_style_text, BrowserBackend, MountMode, StyleBackend, StyleRoot};
 use crate::cursor::Cursor;
 use crate::dyn_css::{StyleContent, StyleId};
+use crate::error::{report_error, StyleError};
+use crate::hooks::DynStyleKind;
+
+// How `StyleManager` evicts styles no component uses anymore.
+//
+// By default, the rules are unmounted as soon as the last component unregisters the style.
+// With `cache_size` or `grace_period`, released styles stay mounted and are reused
+// when the same code is registered again,
+// e.g. by a component toggling between two states.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EvictionPolicy {
+    // Number of released styles kept mounted. The least recently released style is evicted first.
+    // 0 means no limit of the number if `grace_period` is set.
+    pub cache_size: usize,
+    // Released styles older than this are evicted.
+    // Expiration is checked only on registration, unregistration or by `StyleManager::evict_expired`,
+    // so the app calls `evict_expired` from a timer to evict styles while the app is idle.
+    // Works with or without `cache_size`.
+    pub grace_period: Option<Duration>,
+    // Hard cap on mounted styles including released ones.
+    // Released styles are evicted to make room.
+    // Styles beyond the cap are reported with `StyleError::LimitReached`
+    // and mounted in registration order when other styles are unmounted.
+    pub max_live_styles: Option<usize>,
+}
+
+impl EvictionPolicy {
+    // Whether released styles are kept mounted for a while.
+    fn keeps_released(&self) -> bool {
+        self.cache_size > 0 || self.grace_period.is_some()
+    }
+}
+
+// Read-only state of a style managed by `StyleManager` for debugging.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StyleSnapshot {
+    pub style_id: StyleId,
+    pub code: String,
+    // Number of registrations referencing the style.
+    // 0 for released styles kept by the eviction policy.
+    pub ref_count: usize,
+    // Size of the mounted css code.
+    pub bytes: usize,
+    pub created_at: Instant,
+    // False if not mounted because of `max_live_styles`.
+    pub mounted: bool,
+}
 
 struct StyleManagerInner {
+    backend: Box<dyn StyleBackend>,
     managed_ids: HashMap<String, StyleContent>,
+    // Styles mounted before the manager starts, e.g. rendered by the server.
+    // Maps style id to css code.
+    // They are not referenced by any component until the same code is registered.
+    adopted_ids: HashMap<StyleId, String>,
+    // Handles of registrations not unregistered yet.
+    // Unregistering the same registration twice is ignored.
+    live_handles: HashSet<u64>,
+    policy: EvictionPolicy,
+    // Codes of released styles kept mounted, with the released time.
+    // The front is the least recently released.
+    released: VecDeque<(String, Instant)>,
+    // Rules of styles not mounted because of `max_live_styles` by code.
+    unmounted_rules: HashMap<String, Vec<String>>,
+    // Errors not returned to the caller, e.g. failures of evicting other styles.
+    // Reported to the error hook after the borrow of the manager is released.
+    pending_errors: Vec<StyleError>,
+}
+impl StyleManagerInner {
+    fn new(mut backend: Box<dyn StyleBackend>) -> Self {
+        let mut pending_errors = vec![];
+        let adopted_ids = match backend.adopt() {
+            Ok(adopted) => adopted.into_iter().collect(),
+            Err(error) => {
+                pending_errors.push(error);
+                HashMap::new()
+            }
+        };
+        Self {
+            backend,
+            managed_ids: HashMap::new(),
+            adopted_ids,
+            live_handles: HashSet::new(),
+            policy: EvictionPolicy::default(),
+            released: VecDeque::new(),
+            unmounted_rules: HashMap::new(),
+            pending_errors,
+        }
+    }
+
+    // Add a reference to the managed style of the code
+    // and return the content with the handle of the new registration.
+    fn acquire(&mut self, code: &str) -> StyleContent {
+        // Handles are unique across managers,
+        // so that registrations of other managers are not confused with own ones.
+        static NEXT_HANDLE: AtomicU64 = AtomicU64::new(1);
+        let handle = NEXT_HANDLE.fetch_add(1, Ordering::Relaxed);
+        self.live_handles.insert(handle);
+
+        // Reuse the released style.
+        self.released
+            .retain(|(released_code, _)| released_code != code);
+
+        let managed_content = self.managed_ids.get_mut(code).unwrap();
+        managed_content.increment();
+        managed_content.with_handle(handle)
+    }
+
+    // Remove the reference of the registration.
+    // When the last reference disappears, the style is kept in the cache of released styles
+    // or unmounted according to the eviction policy.
+    fn release(&mut self, content: &StyleContent) -> Result<(), StyleError> {
+        if !self.live_handles.remove(&content.handle()) {
+            return Err(StyleError::NotRegistered(content.style_id()));
+        }
+        let code = content.code();
+        if let Some(managed_content) = self.managed_ids.get_mut(&code) {
+            if managed_content.decrement() {
+                if self.policy.keeps_released() && !self.unmounted_rules.contains_key(&code) {
+                    self.released.push_back((code, Instant::now()));
+                    self.evict_expired();
+                } else {
+                    self.remove(&code)?;
+                    self.mount_unmounted();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Stop managing the style of the code and unmount it.
+    fn remove(&mut self, code: &str) -> Result<(), StyleError> {
+        if let Some(content) = self.managed_ids.remove(code) {
+            if self.unmounted_rules.remove(code).is_none() {
+                self.backend.unmount(&content.style_id())?;
+            }
+        }
+        Ok(())
+    }
+
+    // Stop managing the style of the code, keeping the error for the error hook.
+    fn evict(&mut self, code: &str) {
+        if let Err(error) = self.remove(code) {
+            self.pending_errors.push(error);
+        }
+    }
+
+    // Evict released styles beyond the cache size or the grace period.
+    fn evict_expired(&mut self) {
+        let now = Instant::now();
+        while let Some((code, released_at)) = self.released.front() {
+            let expired = self
+                .policy
+                .grace_period
+                .is_some_and(|grace_period| now.duration_since(*released_at) >= grace_period);
+            let over_cache_size =
+                self.policy.cache_size > 0 && self.released.len() > self.policy.cache_size;
+            if !expired && !over_cache_size {
+                break;
+            }
+            let code = code.clone();
+            self.released.pop_front();
+            self.evict(&code);
+        }
+        self.mount_unmounted();
+    }
+
+    // Mount styles not mounted because of `max_live_styles` in registration order
+    // while there is room, evicting released styles.
+    // A style failing to mount stays unmounted until the next chance.
+    fn mount_unmounted(&mut self) {
+        let mut codes = self.unmounted_rules.keys().cloned().collect::<Vec<_>>();
+        codes.sort_by_key(|code| {
+            let content = &self.managed_ids[code];
+            (content.created_at(), content.style_id().id().to_owned())
+        });
+        for code in codes {
+            if !self.has_room() {
+                break;
+            }
+            let style_id = self.managed_ids[&code].style_id();
+            let rules = self.unmounted_rules[&code].clone();
+            match self.backend.mount(&style_id, rules) {
+                Ok(()) => {
+                    self.unmounted_rules.remove(&code);
+                }
+                Err(error) => {
+                    self.pending_errors.push(error);
+                    break;
+                }
+            }
+        }
+    }
+
+    // Number of styles mounted by the manager.
+    fn mounted_count(&self) -> usize {
+        self.managed_ids.len() - self.unmounted_rules.len()
+    }
+
+    // Start managing the style of the code.
+    // If the style is adopted, reuse it instead of mounting again.
+    // `new_style_id` only returns the id of the adopted style with the same css.
+    // If mounting fails, the style is not managed.
+    fn insert(
+        &mut self,
+        code: &str,
+        style_id: StyleId,
+        rules: Vec<String>,
+    ) -> Result<(), StyleError> {
+        let bytes = rules.iter().map(String::len).sum();
+        match self.adopted_ids.remove(&style_id) {
+            Some(_) => (),
+            None if self.has_room() => self.backend.mount(&style_id, rules)?,
+            None => {
+                self.pending_errors
+                    .push(StyleError::LimitReached(style_id.clone()));
+                self.unmounted_rules.insert(code.to_owned(), rules);
+            }
+        }
+
+        self.managed_ids.insert(
+            code.to_owned(),
+            StyleContent::new(style_id, code.to_owned()).with_bytes(bytes),
+        );
+        Ok(())
+    }
+
+    // Whether a new style can be mounted under `max_live_styles`.
+    // Released styles are evicted to make room.
+    fn has_room(&mut self) -> bool {
+        let max_live_styles = match self.policy.max_live_styles {
+            Some(max_live_styles) => max_live_styles,
+            None => return true,
+        };
+        while self.mounted_count() >= max_live_styles {
+            match self.released.pop_front() {
+                Some((code, _)) => self.evict(&code),
+                None => return false,
+            }
+        }
+        true
+    }
+
+    // Derive id from the stable hash of the code,
+    // so the same code gets the same id across page loads and between server and client.
+    // If the id is already used by other code, rehash with salt.
+    // The id of the adopted style is used by other code unless the css is the same,
+    // so that the style is left to the code which rendered it on the server.
+    //
+    // `name` is the readable name of the declaration in debug builds like `my_crate__dyn_css`.
+    // The id is `<name>__<hash>` with the name, otherwise `dynamic-<hash>`.
+    fn new_style_id(&self, code: &str, kind: DynStyleKind, name: Option<&str>) -> StyleId {
+        let mut salt = 0;
+        loop {
+            let id = hashed_id(code, salt, 8);
+            let style_id = match name {
+                Some(name) => StyleId::new(&format!("{name}__{id}")),
+                None => StyleId::new(&format!("dynamic-{id}")),
+            };
+            let managed = self
+                .managed_ids
+                .values()
+                .any(|content| content.style_id() == style_id);
+            let adopted_by_other = match self.adopted_ids.get(&style_id) {
+                Some(css) => !self.is_same_css(css, code, kind, &style_id),
+                None => false,
+            };
+            if !managed && !adopted_by_other {
+                break style_id;
+            }
+            salt += 1;
+        }
+    }
+
+    // Rules of the code scoped with the id.
+    fn rules(
+        &self,
+        code: &str,
+        kind: DynStyleKind,
+        style_id: &StyleId,
+    ) -> Result<Vec<String>, StyleError> {
+        match kind {
+            DynStyleKind::Css => {
+                let css = match RuntimeCss::parse(code) {
+                    Ok(css) => css,
+                    Err((css, _)) => css,
+                };
+                let selector = self.backend.scope_selector(style_id);
+                Ok(TranspiledCss::transpile(&[selector], css).to_rule_strings())
+            }
+            DynStyleKind::Keyframes => keyframes_rules(code, style_id),
+        }
+    }
+
+    // Whether the adopted css is the rules of the code.
+    // The server escapes `</` in the style tags, so the escaped form is the same css.
+    fn is_same_css(&self, css: &str, code: &str, kind: DynStyleKind, style_id: &StyleId) -> bool {
+        match self.rules(code, kind, style_id) {
+            Ok(rules) => {
+                let rules = rules.join("");
+                css == rules || css == escape_style_text(&rules)
+            }
+            Err(_) => false,
+        }
+    }
 }
 
 // StyleManager is intended to be used as a singleton.
 // Singleton instances are accessed via `default()`.
+// A manager provided with `ContextProvider<StyleManager>` is used instead of the singleton
+// by `dyn css!` and `dyn keyframes!` in the descendant components.
+//
+// In register, the same hashed id is generated for strings of
+// the same code and common rules are used.
+// In unregister, the rules are deleted when the last style of the same code disappears.
 //
-// In register, the same random id is generated for strings of
-// the same code and a common style element is used.
-// In unregister, the style element is deleted when the last style of the same code disappears.
+// `StyleManager` itself does not touch the DOM.
+// Mounting rules is delegated to `StyleBackend`.
+// The singleton uses `BrowserBackend` on wasm32 and `CollectBackend` on other targets.
 #[derive(Clone)]
 pub struct StyleManager {
     inner: Rc<RefCell<StyleManagerInner>>,
 }
 impl StyleManager {
-    pub fn register(&self, code: String) -> StyleContent {
+    // Create a new manager apart from the singleton.
+    // Styles already mounted in the backend are adopted.
+    pub fn new(backend: impl StyleBackend + 'static) -> Self {
+        let manager = Self {
+            inner: Rc::new(RefCell::new(StyleManagerInner::new(Box::new(backend)))),
+        };
+        manager.report_pending_errors();
+        manager
+    }
+
+    // Create a new manager mounting styles into the root,
+    // e.g. the shadow root of the web component the app renders into.
+    // Provide it with `ContextProvider<StyleManager>` to use it in the descendant components.
+    pub fn with_root(root: StyleRoot) -> Self {
+        Self::new(BrowserBackend::with_root(MountMode::default(), root))
+    }
+
+    // Replace the backend.
+    // All registered styles are forgotten without unmounting,
+    // so set a new backend for each server side rendering.
+    // Styles already mounted in the new backend are adopted.
+    // Unregistering styles registered before is ignored.
+    // The eviction policy is kept.
+    pub fn set_backend(&self, backend: impl StyleBackend + 'static) {
         let mut inner = self.inner.borrow_mut();
-        let already_exists_ids = inner
-            .managed_ids
-            .values()
-            .map(|content| content.style_id())
-            .collect::<Vec<_>>();
-        let managed_content = inner
-            .managed_ids
-            .entry(code.to_owned())
-            .or_insert_with(|| loop {
-                let id = repeat_with(fastrand::alphabetic)
-                    .take(8)
-                    .collect::<String>();
-                let style_id = StyleId::new(&format!("dynamic-{id}"));
-                if already_exists_ids.contains(&style_id) {
-                    continue;
-                }
+        let policy = inner.policy.clone();
+        *inner = StyleManagerInner::new(Box::new(backend));
+        inner.policy = policy;
+        drop(inner);
+        self.report_pending_errors();
+    }
 
-                let css = match RuntimeCss::parse(&code) {
-                    Ok(css) => css,
-                    Err((css, _)) => css,
-                };
-                let css = TranspiledCss::transpile(&[format!(".{}", style_id.id())], css);
-                let css_code = css.to_style_string();
-
-                let document = gloo::utils::document();
-                let head = gloo::utils::head();
-                let style_element = document
-                    .create_element("style")
-                    .unwrap_or_else(|_| panic!("Failed to create style element"));
-                style_element
-                    .set_attribute("data-style", style_id.id())
-                    .unwrap_or_else(|_| panic!("Failed to set style attribute"));
-                style_element.set_text_content(Some(&css_code));
-                head.append_child(&style_element)
-                    .unwrap_or_else(|_| panic!("Failed to mount style element"));
-
-                break StyleContent::new(style_id, code);
-            });
+    // Use the backend for the singleton while the closure runs,
+    // e.g. a new `CollectBackend` for each server side rendering.
+    // Styles registered in the closure are forgotten after it without unmounting,
+    // and the styles and the backend before are restored.
+    pub fn with_backend<R>(backend: impl StyleBackend + 'static, f: impl FnOnce() -> R) -> R {
+        let _scope = Self::default().scope_backend(backend);
+        f()
+    }
 
-        managed_content.increment();
-        managed_content.clone()
+    // Use the backend until the returned scope is dropped,
+    // then restore the styles and the backend before.
+    // Keep the scope across `.await` of an async render on the same thread.
+    // The eviction policy is kept.
+    pub fn scope_backend(&self, backend: impl StyleBackend + 'static) -> BackendScope {
+        let mut inner = StyleManagerInner::new(Box::new(backend));
+        inner.policy = self.inner.borrow().policy.clone();
+        let previous = self.inner.replace(inner);
+        self.report_pending_errors();
+        BackendScope {
+            manager: self.clone(),
+            previous: Some(previous),
+        }
     }
 
-    pub fn register_dyn_keyframes(&self, code: String) -> StyleContent {
+    // Set how styles no component uses anymore are evicted.
+    pub fn set_eviction_policy(&self, policy: EvictionPolicy) {
         let mut inner = self.inner.borrow_mut();
-        let already_exists_ids = inner
-            .managed_ids
-            .values()
-            .map(|content| content.style_id())
-            .collect::<Vec<_>>();
-        let managed_content = inner
-            .managed_ids
-            .entry(code.to_owned())
-            .or_insert_with(|| loop {
-                let id = repeat_with(fastrand::alphabetic)
-                    .take(8)
-                    .collect::<String>();
-                let style_id = StyleId::new(&format!("dynamic-{id}"));
-                if already_exists_ids.contains(&style_id) {
-                    continue;
-                }
+        inner.policy = policy;
+        inner.evict_expired();
+        drop(inner);
+        self.report_pending_errors();
+    }
 
-                let mut cursor = Cursor::new(&code);
-                let mut css_code = String::new();
-                loop {
-                    match cursor.take_until('@') {
-                        Ok(content) => {
-                            css_code += &content;
-
-                            cursor.take('@').ok_or("`@keyframes` is expected").unwrap();
-                            cursor.take_until_whitespace().unwrap();
-                            cursor.take(' ');
-                            css_code += "@keyframes ";
-                            let animation_name = cursor.take_until('{').unwrap();
-                            let animation_name_with_scoped_id =
-                                String::new() + &animation_name + "-" + style_id.id();
-                            css_code += &animation_name_with_scoped_id;
-                        }
-                        Err(content) => {
-                            css_code += &content;
-                            break;
-                        }
-                    }
-                }
+    // Evict released styles older than the grace period.
+    // Expiration is also checked on every registration and unregistration,
+    // but nothing evicts them while the app is idle,
+    // so call this from a timer, e.g. `gloo::timers::callback::Interval`, with `grace_period`.
+    pub fn evict_expired(&self) {
+        self.inner.borrow_mut().evict_expired();
+        self.report_pending_errors();
+    }
 
-                let document = gloo::utils::document();
-                let head = gloo::utils::head();
-                let style_element = document
-                    .create_element("style")
-                    .unwrap_or_else(|_| panic!("Failed to create style element"));
-                style_element
-                    .set_attribute("data-style", style_id.id())
-                    .unwrap_or_else(|_| panic!("Failed to set style attribute"));
-                style_element.set_text_content(Some(&css_code));
-                head.append_child(&style_element)
-                    .unwrap_or_else(|_| panic!("Failed to mount style element"));
-
-                break StyleContent::new(style_id, code);
-            });
+    // Report errors kept in the manager to the error hook.
+    // Called without borrowing the manager, so that the hook can use the manager.
+    fn report_pending_errors(&self) {
+        let mut inner = self.inner.borrow_mut();
+        let mut errors = std::mem::take(&mut inner.pending_errors);
+        errors.append(&mut inner.backend.take_errors());
+        drop(inner);
+        for error in errors {
+            report_error(&error);
+        }
+    }
 
-        managed_content.increment();
-        managed_content.clone()
+    // Get the id of the style for the code if it is already mounted,
+    // either registered by other components or adopted from the server rendered html.
+    //
+    // Used for the first render before the effect registers the style,
+    // so that hydration does not flash unstyled content.
+    pub fn mounted_style_id(&self, code: &str) -> Option<StyleId> {
+        self.mounted_style_id_named(code, DynStyleKind::Css, None)
     }
 
-    pub fn unregister(&self, content: StyleContent) {
+    pub(crate) fn mounted_style_id_named(
+        &self,
+        code: &str,
+        kind: DynStyleKind,
+        name: Option<&str>,
+    ) -> Option<StyleId> {
+        let inner = self.inner.borrow();
+        if let Some(content) = inner.managed_ids.get(code) {
+            return Some(content.style_id());
+        }
+        let style_id = inner.new_style_id(code, kind, name);
+        inner
+            .adopted_ids
+            .contains_key(&style_id)
+            .then_some(style_id)
+    }
+
+    // Unmount adopted styles which are not registered by any component.
+    // Call this after the first render of the hydrated app.
+    pub fn finish_hydration(&self) {
         let mut inner = self.inner.borrow_mut();
-        let managed_ids = &mut inner.managed_ids;
-        let document = gloo::utils::document();
-        let code = content.code();
-        if let Some(content) = managed_ids.get_mut(&code) {
-            if content.decrement() {
-                if let Some(style) = document
-                    .query_selector(&format!("style[data-style={}]", content.style_id().id()))
-                    .unwrap_or_else(|_| panic!("Failed to query selector"))
-                {
-                    if let Some(parent) = style.parent_element() {
-                        parent
-                            .remove_child(&style)
-                            .unwrap_or_else(|_| panic!("Failed to remove style"));
-                    }
-                }
-                managed_ids.remove(&code);
+        let adopted_ids = std::mem::take(&mut inner.adopted_ids);
+        for style_id in adopted_ids.keys() {
+            if let Err(error) = inner.backend.unmount(style_id) {
+                inner.pending_errors.push(error);
             }
         }
+        drop(inner);
+        self.report_pending_errors();
+    }
+
+    // CSP nonce of the style elements of the backend.
+    // Errors are reported to the error hook.
+    pub fn nonce(&self) -> Option<String> {
+        let result = self.inner.borrow_mut().backend.nonce();
+        result.unwrap_or_else(|error| {
+            report_error(&error);
+            None
+        })
+    }
+
+    // Whether styles are registered while rendering instead of in effects.
+    pub fn register_on_render(&self) -> bool {
+        self.inner.borrow().backend.register_on_render()
+    }
+
+    // Register the style of `dyn css!` code.
+    // Errors are reported to the error hook,
+    // and the returned style has the id but is not mounted.
+    pub fn register(&self, code: String) -> StyleContent {
+        self.register_named(code, DynStyleKind::Css, None)
+    }
+
+    pub fn try_register(&self, code: String) -> Result<StyleContent, StyleError> {
+        self.try_register_named(code, DynStyleKind::Css, None)
+    }
+
+    // Register the style of `dyn keyframes!` code.
+    // Errors are reported to the error hook,
+    // and the returned style has the id but is not mounted.
+    pub fn register_dyn_keyframes(&self, code: String) -> StyleContent {
+        self.register_named(code, DynStyleKind::Keyframes, None)
+    }
+
+    pub fn try_register_dyn_keyframes(&self, code: String) -> Result<StyleContent, StyleError> {
+        self.try_register_named(code, DynStyleKind::Keyframes, None)
+    }
+
+    // Register the style with the readable name of the declaration for the id.
+    // The same code shares the style and the id of the first registration.
+    pub(crate) fn register_named(
+        &self,
+        code: String,
+        kind: DynStyleKind,
+        name: Option<&str>,
+    ) -> StyleContent {
+        let result = self.try_register_named(code.clone(), kind, name);
+        self.or_report(result, code, kind, name)
+    }
+
+    pub(crate) fn try_register_named(
+        &self,
+        code: String,
+        kind: DynStyleKind,
+        name: Option<&str>,
+    ) -> Result<StyleContent, StyleError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.evict_expired();
+        let result = if inner.managed_ids.contains_key(&code) {
+            Ok(())
+        } else {
+            let style_id = inner.new_style_id(&code, kind, name);
+            inner
+                .rules(&code, kind, &style_id)
+                .and_then(|rules| inner.insert(&code, style_id, rules))
+        };
+        let result = result.map(|_| inner.acquire(&code));
+        drop(inner);
+        self.report_pending_errors();
+        result
+    }
+
+    // Report the error of registration and degrade to the style not mounted.
+    // Unregistering the degraded style does nothing.
+    fn or_report(
+        &self,
+        result: Result<StyleContent, StyleError>,
+        code: String,
+        kind: DynStyleKind,
+        name: Option<&str>,
+    ) -> StyleContent {
+        result.unwrap_or_else(|error| {
+            report_error(&error);
+            let style_id = self.inner.borrow().new_style_id(&code, kind, name);
+            StyleContent::new(style_id, code)
+        })
+    }
+
+    // Unregister the style returned by `register` or `register_dyn_keyframes`.
+    // Unregistering the same registration twice does nothing.
+    // Other errors are reported to the error hook.
+    pub fn unregister(&self, content: StyleContent) {
+        match self.try_unregister(content) {
+            Ok(()) | Err(StyleError::NotRegistered(_)) => (),
+            Err(error) => report_error(&error),
+        }
+    }
+
+    // Unregister the style returned by `register` or `register_dyn_keyframes`.
+    // Returns `StyleError::NotRegistered` if already unregistered.
+    pub fn try_unregister(&self, content: StyleContent) -> Result<(), StyleError> {
+        let result = self.inner.borrow_mut().release(&content);
+        self.report_pending_errors();
+        result
+    }
+
+    // Snapshot of the managed styles in creation order.
+    pub fn snapshot(&self) -> Vec<StyleSnapshot> {
+        let inner = self.inner.borrow();
+        let mut snapshot = inner
+            .managed_ids
+            .values()
+            .map(|content| StyleSnapshot {
+                style_id: content.style_id(),
+                code: content.code(),
+                ref_count: content.count(),
+                bytes: content.bytes(),
+                created_at: content.created_at(),
+                mounted: !inner.unmounted_rules.contains_key(&content.code()),
+            })
+            .collect::<Vec<_>>();
+        snapshot.sort_by(|a, b| {
+            a.created_at
+                .cmp(&b.created_at)
+                .then_with(|| a.style_id.id().cmp(b.style_id.id()))
+        });
+        snapshot
+    }
+
+    // Reference count of the style for the code. 0 if not registered.
+    pub fn ref_count(&self, code: &str) -> usize {
+        self.inner
+            .borrow()
+            .managed_ids
+            .get(code)
+            .map_or(0, |content| content.count())
+    }
+}
+// Scope of the backend returned by `StyleManager::scope_backend`.
+// Dropping it restores the styles and the backend before the scope.
+pub struct BackendScope {
+    manager: StyleManager,
+    previous: Option<StyleManagerInner>,
+}
+impl Drop for BackendScope {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            // Drop the scoped state after the borrow is released.
+            let scoped = self.manager.inner.replace(previous);
+            drop(scoped);
+        }
+    }
+}
+
+impl PartialEq for StyleManager {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
     }
 }
 impl Default for StyleManager {
     fn default() -> Self {
         thread_local! {
-            static MANAGER: Lazy<StyleManager> = Lazy::new(|| StyleManager { inner: Rc::new(RefCell::new(StyleManagerInner { managed_ids: HashMap::new()}))});
+            static MANAGER: Lazy<StyleManager> = Lazy::new(|| {
+                #[cfg(target_arch = "wasm32")]
+                let backend = BrowserBackend::default();
+                #[cfg(not(target_arch = "wasm32"))]
+                let backend = CollectBackend::default();
+                StyleManager::new(backend)
+            });
         }
         MANAGER.with(|m| (*m).clone())
     }
 }
+
+// Split the code of `dyn keyframes!` into one rule per `@keyframes` block,
+// scoping each animation name with the style id.
+fn keyframes_rules(code: &str, style_id: &StyleId) -> Result<Vec<String>, StyleError> {
+    let mut cursor = Cursor::new(code);
+    let mut rules = vec![];
+    let mut css_code = String::new();
+    loop {
+        match cursor.take_until('@') {
+            Ok(content) => {
+                css_code += &content;
+                if !css_code.trim().is_empty() {
+                    rules.push(css_code);
+                }
+                css_code = String::new();
+
+                cursor.take('@');
+                match cursor.take_until_whitespace() {
+                    Some(at_keyword) if at_keyword.ends_with("keyframes") => (),
+                    _ => {
+                        return Err(StyleError::InvalidKeyframes(
+                            "`@keyframes` is expected".into(),
+                        ))
+                    }
+                }
+                css_code += "@keyframes ";
+                // whitespace around the name like `@keyframes spin {` is not a part of it
+                let animation_name = cursor.take_until('{').map_err(|_| {
+                    StyleError::InvalidKeyframes("`{` is expected after the animation name".into())
+                })?;
+                let animation_name = animation_name.trim();
+                if animation_name.is_empty() {
+                    return Err(StyleError::InvalidKeyframes(
+                        "animation name is expected".into(),
+                    ));
+                }
+                let animation_name_with_scoped_id =
+                    String::new() + animation_name + "-" + style_id.id();
+                css_code += &animation_name_with_scoped_id;
+            }
+            Err(content) => {
+                css_code += &content;
+                if !css_code.trim().is_empty() {
+                    rules.push(css_code);
+                }
+                break;
+            }
+        }
+    }
+    Ok(rules)
+}
@@ -0,0 +1,977 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::backend::*;
+use super::dyn_css::*;
+use super::dyn_style::*;
+use super::error::*;
+use super::runtime_manager::*;
+
+#[test]
+fn test_collect_backend_1() {
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    assert!(manager.register_on_render());
+
+    let style = manager.register("background: black;".into());
+    let id = style.style_id();
+    assert!(id.id().starts_with("dynamic-"));
+
+    assert_eq!(
+        backend.styles(),
+        vec![(id.clone(), format!(".{}{{background:black;}}", id.id()))]
+    );
+    assert_eq!(
+        backend.to_style_tags(),
+        format!(
+            r#"<style data-style="{0}">.{0}{{background:black;}}</style>"#,
+            id.id()
+        )
+    );
+}
+
+#[test]
+fn test_collect_backend_2() {
+    // same code shares one style and is unmounted with the last unregister
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let style_a = manager.register("color: red;".into());
+    let style_b = manager.register("color: red;".into());
+    let style_c = manager.register("color: blue;".into());
+    assert_eq!(style_a.style_id(), style_b.style_id());
+    assert_ne!(style_a.style_id(), style_c.style_id());
+    assert_eq!(backend.styles().len(), 2);
+
+    manager.unregister(style_a);
+    assert_eq!(backend.styles().len(), 2);
+    manager.unregister(style_b);
+    assert_eq!(backend.styles().len(), 1);
+    manager.unregister(style_c);
+    assert!(backend.styles().is_empty());
+    assert_eq!(backend.to_style_tags(), "");
+}
+
+#[test]
+fn test_collect_backend_3() {
+    // ids are reproducible across managers
+    let manager_a = StyleManager::new(CollectBackend::new());
+    let manager_b = StyleManager::new(CollectBackend::new());
+    assert_eq!(
+        manager_a.register("color: red;".into()).style_id(),
+        manager_b.register("color: red;".into()).style_id()
+    );
+}
+
+#[test]
+fn test_collect_backend_4() {
+    // keyframes are scoped with the dynamic id
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let style = manager.register_dyn_keyframes(
+        "@keyframes anim{to{transform:translateX(10px);}}@keyframes fade{to{opacity:0;}}".into(),
+    );
+    let id = style.style_id();
+    assert_eq!(
+        backend.styles(),
+        vec![(
+            id.clone(),
+            format!(
+                "@keyframes anim-{0}{{to{{transform:translateX(10px);}}}}@keyframes fade-{0}{{to{{opacity:0;}}}}",
+                id.id()
+            )
+        )]
+    );
+}
+
+#[test]
+fn test_collect_backend_5() {
+    // `</` is escaped not to close the style tag
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    manager.register(r#"content: "</style><script>";"#.into());
+    assert!(!backend.to_style_tags().contains("</style><script>"));
+}
+
+#[test]
+fn test_set_backend_1() {
+    // replacing backend forgets registered styles
+    let manager = StyleManager::new(CollectBackend::new());
+    manager.register("color: red;".into());
+
+    let backend = CollectBackend::new();
+    manager.set_backend(backend.clone());
+    manager.register("color: red;".into());
+    assert_eq!(backend.styles().len(), 1);
+}
+
+#[test]
+fn test_set_backend_2() {
+    // styles of a render in the scope of the backend do not leak into the next render
+    // the style is alive until the rendered app is dropped
+    let render = |code: &str| DynStyle::new(code.into());
+    let before = StyleManager::default().register("color: black;".into());
+
+    let first = CollectBackend::new();
+    let red = StyleManager::with_backend(first.clone(), || render("color: red;"));
+    let second = CollectBackend::new();
+    let blue = StyleManager::with_backend(second.clone(), || render("color: blue;"));
+
+    assert_eq!(
+        first
+            .styles()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>(),
+        vec![red.style_id()]
+    );
+    assert_eq!(
+        second
+            .styles()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>(),
+        vec![blue.style_id()]
+    );
+    drop((red, blue));
+
+    // the styles before the scope are restored
+    let manager = StyleManager::default();
+    assert_eq!(manager.ref_count("color: black;"), 1);
+    assert_eq!(manager.ref_count("color: red;"), 0);
+    manager.unregister(before);
+    assert_eq!(manager.ref_count("color: black;"), 0);
+
+    // the scope keeps the backend across an async render until it is dropped
+    let third = CollectBackend::new();
+    let scope = manager.scope_backend(third.clone());
+    let style = manager.register("color: green;".into());
+    drop(scope);
+    manager.unregister(style);
+    assert_eq!(third.styles().len(), 1);
+    assert_eq!(manager.ref_count("color: green;"), 0);
+}
+
+#[test]
+fn test_resolve_animation_names_1() {
+    let mut dyn_names_map = HashMap::new();
+    dyn_names_map.insert("dynamic-AbCdEfGh".to_string(), vec!["slide".to_string()]);
+
+    let code = resolve_animation_names(
+        "animation: ##slide## 1s, ##anim## 2s, ##unknown## 3s; color: #fff;",
+        &["anim".to_string()],
+        &dyn_names_map,
+    );
+    assert_eq!(
+        code,
+        "animation: slide-dynamic-AbCdEfGh 1s, anim 2s, ##unknown## 3s; color: #fff;"
+    );
+}
+
+#[test]
+fn test_hydration_1() {
+    // styles rendered by the server are adopted and reused by the client
+    let backend = CollectBackend::new();
+    let server = StyleManager::new(backend.clone());
+    let server_style = server.register("color: red;".into());
+    server.register("color: blue;".into());
+    let server_styles = backend.styles();
+
+    let client = StyleManager::new(backend.clone());
+    assert_eq!(
+        client.mounted_style_id("color: red;"),
+        Some(server_style.style_id())
+    );
+    assert_eq!(client.mounted_style_id("color: green;"), None);
+
+    let client_style = client.register("color: red;".into());
+    assert_eq!(client_style.style_id(), server_style.style_id());
+    assert_eq!(backend.styles(), server_styles);
+
+    // orphans are removed after hydration
+    client.finish_hydration();
+    assert_eq!(
+        backend.styles(),
+        vec![(server_style.style_id(), server_styles[0].1.clone())]
+    );
+    assert_eq!(client.mounted_style_id("color: blue;"), None);
+
+    // adopted style is unmounted with the last unregister
+    client.unregister(client_style);
+    assert!(backend.styles().is_empty());
+}
+
+#[test]
+fn test_hydration_2() {
+    // adopted style with different css is left to its code and not taken by other code
+    let backend = CollectBackend::new();
+    let server = StyleManager::new(backend.clone());
+    let style_id = server.register("color: red;".into()).style_id();
+
+    let stale = CollectBackend::new();
+    let mut stale_backend = stale.clone();
+    StyleBackend::mount(&mut stale_backend, &style_id, vec![".stale{}".into()]).unwrap();
+
+    let client = StyleManager::new(stale.clone());
+    assert_eq!(client.mounted_style_id("color: red;"), None);
+    let client_style = client.register("color: red;".into());
+    assert_ne!(client_style.style_id(), style_id);
+    assert_eq!(
+        stale.styles()[0],
+        (style_id.clone(), ".stale{}".to_string())
+    );
+
+    client.finish_hydration();
+    assert_eq!(stale.styles().len(), 1);
+    assert_eq!(stale.styles()[0].0, client_style.style_id());
+}
+
+#[test]
+fn test_hydration_3() {
+    // style tags escape `</` and the escaped css is adopted as the same css
+    let backend = CollectBackend::new();
+    let server = StyleManager::new(backend.clone());
+    let server_style = server.register(r#"content: "</style>";"#.into());
+    let (style_id, css) = backend.styles()[0].clone();
+    assert!(backend.to_style_tags().contains(r#"content:"<\/style>""#));
+
+    let adopted = CollectBackend::new();
+    let mut adopted_backend = adopted.clone();
+    let escaped = css.replace("</", "<\\/");
+    StyleBackend::mount(&mut adopted_backend, &style_id, vec![escaped.clone()]).unwrap();
+
+    let client = StyleManager::new(adopted.clone());
+    assert_eq!(
+        client.mounted_style_id(r#"content: "</style>";"#),
+        Some(server_style.style_id())
+    );
+    client.register(r#"content: "</style>";"#.into());
+    assert_eq!(adopted.styles(), vec![(style_id, escaped)]);
+}
+
+#[test]
+fn test_registry_1() {
+    // reference count follows register and unregister
+    let manager = StyleManager::new(CollectBackend::new());
+    assert_eq!(manager.ref_count("color: red;"), 0);
+
+    let style_a = manager.register("color: red;".into());
+    let style_b = manager.register("color: red;".into());
+    assert_eq!(manager.ref_count("color: red;"), 2);
+
+    manager.unregister(style_a);
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    manager.unregister(style_b);
+    assert_eq!(manager.ref_count("color: red;"), 0);
+}
+
+#[test]
+fn test_registry_2() {
+    // unregistering the same registration twice does not release other registrations
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let style_a = manager.register("color: red;".into());
+    let style_b = manager.register("color: red;".into());
+    manager.unregister(style_a.clone());
+    manager.unregister(style_a);
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.styles().len(), 1);
+
+    manager.unregister(style_b.clone());
+    manager.unregister(style_b);
+    assert_eq!(manager.ref_count("color: red;"), 0);
+    assert!(backend.styles().is_empty());
+}
+
+#[test]
+fn test_registry_3() {
+    // unregistering after the style is unmounted does nothing
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let style_a = manager.register("color: red;".into());
+    manager.unregister(style_a.clone());
+    let style_b = manager.register("color: red;".into());
+    manager.unregister(style_a);
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.styles().len(), 1);
+
+    manager.unregister(style_b);
+    assert!(backend.styles().is_empty());
+}
+
+#[test]
+fn test_registry_4() {
+    // unregistering styles of other managers does nothing
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    let other = StyleManager::new(CollectBackend::new());
+
+    manager.register("color: red;".into());
+    let other_style = other.register("color: red;".into());
+    manager.unregister(other_style.clone());
+    manager.unregister(other_style);
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.styles().len(), 1);
+}
+
+#[test]
+fn test_registry_5() {
+    // styles registered before `set_backend` are not unregistered from the new backend
+    let manager = StyleManager::new(CollectBackend::new());
+    let old_style = manager.register("color: red;".into());
+
+    let backend = CollectBackend::new();
+    manager.set_backend(backend.clone());
+    manager.register("color: red;".into());
+    manager.unregister(old_style);
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.styles().len(), 1);
+}
+
+#[test]
+fn test_registry_6() {
+    // dyn keyframes share the reference counting with dyn css
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    let code = "@keyframes anim{to{opacity:0;}}";
+
+    let style_a = manager.register_dyn_keyframes(code.into());
+    let style_b = manager.register_dyn_keyframes(code.into());
+    assert_eq!(style_a.style_id(), style_b.style_id());
+    assert_eq!(manager.ref_count(code), 2);
+    assert_eq!(backend.styles().len(), 1);
+
+    manager.unregister(style_a.clone());
+    manager.unregister(style_a);
+    assert_eq!(backend.styles().len(), 1);
+    manager.unregister(style_b);
+    assert!(backend.styles().is_empty());
+}
+
+#[test]
+fn test_registry_7() {
+    // different code gets different id and is unmounted independently
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let style_a = manager.register("color: red;".into());
+    let style_b = manager.register_dyn_keyframes("@keyframes anim{to{opacity:0;}}".into());
+    assert_ne!(style_a.style_id(), style_b.style_id());
+
+    manager.unregister(style_a);
+    assert_eq!(
+        backend
+            .styles()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>(),
+        vec![style_b.style_id()]
+    );
+}
+
+#[test]
+fn test_style_content_decrement_1() {
+    // decrement never underflows
+    let mut content = StyleContent::new(StyleId::new("dynamic-AbCdEfGh"), "color: red;".into());
+    content.increment();
+    assert!(content.decrement());
+    assert!(content.decrement());
+    assert_eq!(content.count(), 0);
+}
+
+#[test]
+fn test_scope_selector_1() {
+    // rules are scoped with the selector of the backend
+    #[derive(Clone, Default)]
+    struct HostBackend(CollectBackend);
+    impl StyleBackend for HostBackend {
+        fn mount(&mut self, style_id: &StyleId, rules: Vec<String>) -> Result<(), StyleError> {
+            self.0.mount(style_id, rules)
+        }
+        fn unmount(&mut self, style_id: &StyleId) -> Result<(), StyleError> {
+            self.0.unmount(style_id)
+        }
+        fn scope_selector(&self, style_id: &StyleId) -> String {
+            format!(":host(.{})", style_id.id())
+        }
+    }
+
+    let backend = HostBackend::default();
+    let manager = StyleManager::new(backend.clone());
+    let id = manager.register("color: red;".into()).style_id();
+    assert_eq!(
+        backend.0.styles(),
+        vec![(id.clone(), format!(":host(.{}){{color:red;}}", id.id()))]
+    );
+}
+
+#[test]
+fn test_collect_backend_6() {
+    // style tags with CSP nonce
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let id = manager.register("color: red;".into()).style_id();
+    assert_eq!(
+        backend.to_style_tags_with_nonce(r#"abc"123"#),
+        format!(
+            r#"<style data-style="{0}" nonce="abc&quot;123">.{0}{{color:red;}}</style>"#,
+            id.id()
+        )
+    );
+}
+
+#[test]
+fn test_collect_backend_7() {
+    // whitespace around the animation name is not a part of the scoped name
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let code = "@keyframes spin { from { opacity: 0; } to { opacity: 1; } }";
+    let style = manager.register_dyn_keyframes(code.into());
+    let id = style.style_id();
+    assert_eq!(
+        backend.styles(),
+        vec![(
+            id.clone(),
+            format!(
+                "@keyframes spin-{}{{ from {{ opacity: 0; }} to {{ opacity: 1; }} }}",
+                id.id()
+            )
+        )]
+    );
+    assert_eq!(super::hooks::keyframes_names(code), vec!["spin"]);
+
+    assert!(matches!(
+        manager.try_register_dyn_keyframes("@keyframes {to{opacity:0;}}".into()),
+        Err(StyleError::InvalidKeyframes(_))
+    ));
+}
+
+#[test]
+fn test_eviction_1() {
+    // released styles are kept mounted and reused
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 1,
+        ..Default::default()
+    });
+
+    let style = manager.register("color: red;".into());
+    manager.unregister(style);
+    assert_eq!(manager.ref_count("color: red;"), 0);
+    let styles = backend.styles();
+    assert_eq!(styles.len(), 1);
+
+    let style = manager.register("color: red;".into());
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.styles(), styles);
+
+    // the least recently released style is evicted beyond the cache size
+    let style_blue = manager.register("color: blue;".into());
+    manager.unregister(style);
+    manager.unregister(style_blue.clone());
+    assert_eq!(
+        backend
+            .styles()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>(),
+        vec![style_blue.style_id()]
+    );
+}
+
+#[test]
+fn test_eviction_2() {
+    // released styles are evicted after the grace period
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 8,
+        grace_period: Some(Duration::from_secs(3600)),
+        ..Default::default()
+    });
+    let style = manager.register("color: red;".into());
+    manager.unregister(style);
+    manager.evict_expired();
+    assert_eq!(backend.styles().len(), 1);
+
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 8,
+        grace_period: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    assert!(backend.styles().is_empty());
+    assert_eq!(manager.ref_count("color: red;"), 0);
+}
+
+#[test]
+fn test_eviction_3() {
+    // released styles are evicted to keep the hard cap
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 8,
+        max_live_styles: Some(2),
+        ..Default::default()
+    });
+
+    let style_red = manager.register("color: red;".into());
+    let style_blue = manager.register("color: blue;".into());
+    manager.unregister(style_red);
+    let style_green = manager.register("color: green;".into());
+    assert_eq!(
+        backend
+            .styles()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>(),
+        vec![style_blue.style_id(), style_green.style_id()]
+    );
+
+    // styles beyond the cap are not mounted while used
+    let style_white = manager.register("color: white;".into());
+    assert_eq!(manager.ref_count("color: white;"), 1);
+    assert_eq!(backend.styles().len(), 2);
+    manager.unregister(style_white);
+    assert_eq!(manager.ref_count("color: white;"), 0);
+    assert_eq!(backend.styles().len(), 2);
+
+    manager.unregister(style_blue);
+    manager.unregister(style_green);
+    let style_black = manager.register("color: black;".into());
+    assert_eq!(backend.styles().len(), 2);
+    assert!(backend
+        .styles()
+        .iter()
+        .any(|(id, _)| id == &style_black.style_id()));
+}
+
+#[test]
+fn test_eviction_4() {
+    // the grace period keeps released styles without the cache size
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 0,
+        grace_period: Some(Duration::from_secs(3600)),
+        ..Default::default()
+    });
+    let style_red = manager.register("color: red;".into());
+    let style_blue = manager.register("color: blue;".into());
+    manager.unregister(style_red);
+    manager.unregister(style_blue);
+    manager.evict_expired();
+    assert_eq!(backend.styles().len(), 2);
+
+    // reused while kept
+    let style_red = manager.register("color: red;".into());
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.styles().len(), 2);
+    manager.unregister(style_red);
+
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 0,
+        grace_period: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    assert!(backend.styles().is_empty());
+}
+
+#[test]
+fn test_eviction_5() {
+    // styles beyond the cap are reported and mounted when other styles are unmounted
+    let errors = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    set_error_hook({
+        let errors = errors.clone();
+        move |error| errors.borrow_mut().push(error.clone())
+    });
+
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+    manager.set_eviction_policy(EvictionPolicy {
+        max_live_styles: Some(2),
+        ..Default::default()
+    });
+    let mounted_ids = || {
+        backend
+            .styles()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>()
+    };
+
+    let style_red = manager.register("color: red;".into());
+    let style_blue = manager.register("color: blue;".into());
+    let style_green = manager.register("color: green;".into());
+    let style_white = manager.register("color: white;".into());
+    assert_eq!(
+        *errors.borrow(),
+        vec![
+            StyleError::LimitReached(style_green.style_id()),
+            StyleError::LimitReached(style_white.style_id()),
+        ]
+    );
+    assert_eq!(
+        mounted_ids(),
+        vec![style_red.style_id(), style_blue.style_id()]
+    );
+
+    // in registration order
+    manager.unregister(style_red);
+    assert_eq!(
+        mounted_ids(),
+        vec![style_blue.style_id(), style_green.style_id()]
+    );
+
+    // released styles are evicted for the styles in use
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 8,
+        max_live_styles: Some(2),
+        ..Default::default()
+    });
+    manager.unregister(style_blue);
+    assert_eq!(
+        mounted_ids(),
+        vec![style_green.style_id(), style_white.style_id()]
+    );
+    assert!(manager.snapshot().iter().all(|style| style.mounted));
+    assert_eq!(errors.borrow().len(), 2);
+
+    reset_error_hook();
+}
+
+#[test]
+fn test_snapshot_1() {
+    let manager = StyleManager::new(CollectBackend::new());
+    manager.set_eviction_policy(EvictionPolicy {
+        cache_size: 1,
+        max_live_styles: Some(2),
+        ..Default::default()
+    });
+    assert!(manager.snapshot().is_empty());
+
+    let style_red = manager.register("color: red;".into());
+    manager.register("color: red;".into());
+    let style_blue = manager.register("color: blue;".into());
+    manager.unregister(style_blue.clone());
+    let style_green = manager.register("color: green;".into());
+    let style_white = manager.register("color: white;".into());
+
+    let snapshot = manager.snapshot();
+    assert_eq!(
+        snapshot
+            .iter()
+            .map(|style| (style.style_id.clone(), style.ref_count, style.mounted))
+            .collect::<Vec<_>>(),
+        vec![
+            (style_red.style_id(), 2, true),
+            (style_green.style_id(), 1, true),
+            (style_white.style_id(), 1, false),
+        ]
+    );
+    assert_eq!(snapshot[0].code, "color: red;");
+    assert_eq!(
+        snapshot[0].bytes,
+        format!(".{}{{color:red;}}", style_red.style_id().id()).len()
+    );
+    assert!(snapshot[0].created_at <= snapshot[1].created_at);
+}
+
+// Backend failing to mount and unmount while `fail` is true.
+#[derive(Clone, Default)]
+struct FailingBackend {
+    collect: CollectBackend,
+    fail: std::rc::Rc<std::cell::Cell<bool>>,
+}
+impl StyleBackend for FailingBackend {
+    fn mount(&mut self, style_id: &StyleId, rules: Vec<String>) -> Result<(), StyleError> {
+        if self.fail.get() {
+            return Err(StyleError::Dom("Failed to mount".into()));
+        }
+        self.collect.mount(style_id, rules)
+    }
+    fn unmount(&mut self, style_id: &StyleId) -> Result<(), StyleError> {
+        if self.fail.get() {
+            return Err(StyleError::Dom("Failed to unmount".into()));
+        }
+        self.collect.unmount(style_id)
+    }
+}
+
+#[test]
+fn test_try_register_1() {
+    // failed mount is returned and the style is not managed
+    let backend = FailingBackend::default();
+    let manager = StyleManager::new(backend.clone());
+
+    backend.fail.set(true);
+    assert_eq!(
+        manager.try_register("color: red;".into()),
+        Err(StyleError::Dom("Failed to mount".into()))
+    );
+    assert_eq!(manager.ref_count("color: red;"), 0);
+
+    backend.fail.set(false);
+    let style = manager.try_register("color: red;".into()).unwrap();
+    assert_eq!(manager.ref_count("color: red;"), 1);
+    assert_eq!(backend.collect.styles().len(), 1);
+    assert_eq!(manager.try_unregister(style.clone()), Ok(()));
+    assert_eq!(
+        manager.try_unregister(style.clone()),
+        Err(StyleError::NotRegistered(style.style_id()))
+    );
+}
+
+#[test]
+fn test_try_register_2() {
+    // malformed dyn keyframes
+    let manager = StyleManager::new(CollectBackend::new());
+    assert!(matches!(
+        manager.try_register_dyn_keyframes("@keyframes anim".into()),
+        Err(StyleError::InvalidKeyframes(_))
+    ));
+    assert!(matches!(
+        manager.try_register_dyn_keyframes("@media{}".into()),
+        Err(StyleError::InvalidKeyframes(_))
+    ));
+    assert!(manager.snapshot().is_empty());
+}
+
+#[test]
+fn test_error_hook_1() {
+    // infallible api reports errors to the hook and degrades
+    let errors = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    set_error_hook({
+        let errors = errors.clone();
+        move |error| errors.borrow_mut().push(error.clone())
+    });
+
+    let backend = FailingBackend::default();
+    let manager = StyleManager::new(backend.clone());
+    let style = manager.register("color: red;".into());
+    manager.unregister(style.clone());
+    assert!(errors.borrow().is_empty());
+
+    backend.fail.set(true);
+    let style = manager.register("color: red;".into());
+    assert!(style.style_id().id().starts_with("dynamic-"));
+    assert_eq!(manager.ref_count("color: red;"), 0);
+    manager.unregister(style);
+
+    let style = manager.register_dyn_keyframes("@keyframes".into());
+    manager.unregister(style);
+    assert_eq!(
+        *errors.borrow(),
+        vec![
+            StyleError::Dom("Failed to mount".into()),
+            StyleError::InvalidKeyframes("`@keyframes` is expected".into()),
+        ]
+    );
+
+    // failed unmount is reported and the style is forgotten
+    backend.fail.set(false);
+    let style = manager.register("color: blue;".into());
+    backend.fail.set(true);
+    manager.unregister(style);
+    assert_eq!(manager.ref_count("color: blue;"), 0);
+    assert_eq!(errors.borrow().len(), 3);
+
+    reset_error_hook();
+}
+
+#[test]
+fn test_error_hook_2() {
+    // errors kept by the backend are reported after the operation
+    #[derive(Default)]
+    struct RejectingBackend {
+        collect: CollectBackend,
+        errors: Vec<StyleError>,
+    }
+    impl StyleBackend for RejectingBackend {
+        fn mount(&mut self, style_id: &StyleId, rules: Vec<String>) -> Result<(), StyleError> {
+            let (accepted, rejected) = rules.into_iter().partition(|rule| !rule.contains("@bad"));
+            for rule in rejected {
+                self.errors
+                    .push(StyleError::Dom(format!("Failed to insert rule: {rule}")));
+            }
+            self.collect.mount(style_id, accepted)
+        }
+        fn unmount(&mut self, style_id: &StyleId) -> Result<(), StyleError> {
+            self.collect.unmount(style_id)
+        }
+        fn take_errors(&mut self) -> Vec<StyleError> {
+            std::mem::take(&mut self.errors)
+        }
+    }
+
+    let errors = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    set_error_hook({
+        let errors = errors.clone();
+        move |error| errors.borrow_mut().push(error.clone())
+    });
+
+    let manager = StyleManager::new(RejectingBackend::default());
+    let style = manager.register("color: red; @bad { color: blue; }".into());
+    assert_eq!(manager.ref_count("color: red; @bad { color: blue; }"), 1);
+    assert!(matches!(
+        &errors.borrow()[..],
+        [StyleError::Dom(message)] if message.starts_with("Failed to insert rule: @bad")
+    ));
+    manager.unregister(style);
+    assert_eq!(errors.borrow().len(), 1);
+
+    reset_error_hook();
+}
+
+#[test]
+fn test_check_dyn_css_1() {
+    // parse errors are reported with the source location
+    let errors = std::rc::Rc::new(std::cell::RefCell::new(vec![]));
+    set_error_hook({
+        let errors = errors.clone();
+        move |error| errors.borrow_mut().push(error.clone())
+    });
+
+    check_dyn_css("color: red;", "src/app.rs:1:1");
+    assert!(errors.borrow().is_empty());
+
+    check_dyn_css("color: red; {", "src/app.rs:2:5");
+    assert!(matches!(
+        &errors.borrow()[..],
+        [StyleError::InvalidCss { code, location, .. }]
+            if code == "color: red; {" && location == "src/app.rs:2:5"
+    ));
+
+    reset_error_hook();
+}
+
+#[test]
+fn test_keyframes_names_1() {
+    assert_eq!(
+        super::hooks::keyframes_names(
+            "@keyframes spin { to { transform: rotate(90deg); } } @media print {} @keyframes fade{to{opacity:0;}}"
+        ),
+        vec!["spin".to_string(), "fade".to_string()]
+    );
+    assert!(super::hooks::keyframes_names("color: red;").is_empty());
+}
+
+#[test]
+fn test_dyn_style_1() {
+    // update re-registers the style and drop unregisters it
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let mut style = DynStyle::with_manager(manager.clone(), "width: 10px;".into());
+    let id = style.style_id();
+    assert_eq!(manager.ref_count("width: 10px;"), 1);
+
+    assert!(!style.update("width: 10px;".into()));
+    assert_eq!(style.style_id(), id);
+
+    assert!(style.update("width: 20px;".into()));
+    assert_ne!(style.style_id(), id);
+    assert_eq!(style.code(), "width: 20px;");
+    assert_eq!(manager.ref_count("width: 10px;"), 0);
+    assert_eq!(manager.ref_count("width: 20px;"), 1);
+    assert_eq!(backend.styles().len(), 1);
+
+    drop(style);
+    assert_eq!(manager.ref_count("width: 20px;"), 0);
+    assert!(backend.styles().is_empty());
+}
+
+#[test]
+fn test_named_style_id_1() {
+    // readable ids with the name of the declaration in debug builds
+    use super::hooks::DynStyleKind;
+
+    let backend = CollectBackend::new();
+    let manager = StyleManager::new(backend.clone());
+
+    let style = manager.register_named(
+        "color: red;".into(),
+        DynStyleKind::Css,
+        Some("my_app__dyn_css"),
+    );
+    let id = style.style_id();
+    assert!(id.id().starts_with("my_app__dyn_css__"));
+    assert_eq!(id.id().len(), "my_app__dyn_css__".len() + 8);
+    assert_eq!(
+        backend.styles(),
+        vec![(id.clone(), format!(".{}{{color:red;}}", id.id()))]
+    );
+
+    // the same code shares the style of the first registration
+    let other = manager.register_named("color: red;".into(), DynStyleKind::Css, Some("other"));
+    assert_eq!(other.style_id(), id);
+    assert_eq!(manager.mounted_style_id("color: red;"), Some(id));
+
+    let keyframes = manager.register_named(
+        "@keyframes anim{to{opacity:0;}}".into(),
+        DynStyleKind::Keyframes,
+        Some("my_app__keyframes"),
+    );
+    assert!(keyframes.style_id().id().starts_with("my_app__keyframes__"));
+}
+
+#[test]
+fn test_named_style_id_2() {
+    // `dyn_style!` handle keeps the readable name on update
+    let mut style = DynStyle::with_name("width: 10px;".into(), Some("my_app__dyn_style"));
+    assert!(style.style_id().id().starts_with("my_app__dyn_style__"));
+
+    assert!(style.update("width: 20px;".into()));
+    assert!(style.style_id().id().starts_with("my_app__dyn_style__"));
+
+    let unnamed = DynStyle::new("width: 30px;".into());
+    assert!(unnamed.style_id().id().starts_with("dynamic-"));
+}
+
+#[test]
+fn test_units_1() {
+    // numbers are rounded to at most 4 fractional digits without trailing zeros
+    use super::units::*;
+
+    assert_eq!(Px(0.0).to_string(), "0px");
+    assert_eq!(Px(-0.0).to_string(), "0px");
+    assert_eq!(Px(10.0).to_string(), "10px");
+    assert_eq!(Vw(0.1 + 0.2).to_string(), "0.3vw");
+    assert_eq!(Px(0.33333).to_string(), "0.3333px");
+    assert_eq!(Px(0.66666).to_string(), "0.6667px");
+    assert_eq!(Px(2.99999).to_string(), "3px");
+    assert_eq!(Px(1.50).to_string(), "1.5px");
+}
+
+#[test]
+fn test_units_2() {
+    // negative numbers keep the sign unless rounded to zero
+    use super::units::*;
+
+    assert_eq!(Px(-1.5).to_string(), "-1.5px");
+    assert_eq!(Px(-12.0).to_string(), "-12px");
+    assert_eq!(Px(-0.66666).to_string(), "-0.6667px");
+    assert_eq!(Px(-0.00001).to_string(), "0px");
+    assert_eq!(Px(-0.00004).to_string(), "0px");
+}
+
+#[test]
+fn test_units_3() {
+    // very large and very small numbers
+    use super::units::*;
+
+    assert_eq!(Px(1e-10).to_string(), "0px");
+    assert_eq!(Px(0.00005).to_string(), "0.0001px");
+    assert_eq!(Px(1e9).to_string(), "1000000000px");
+    assert_eq!(Px(123456789.123456).to_string(), "123456789.1235px");
+    assert!(Px(f64::MAX).to_string().starts_with("17976931348623157"));
+    assert!(!Px(f64::MAX).to_string().contains("inf"));
+    assert_eq!(Px(f64::NAN).to_string(), "0px");
+    assert_eq!(Px(f64::INFINITY).to_string(), "0px");
+}
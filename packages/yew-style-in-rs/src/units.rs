@@ -0,0 +1,293 @@
+//! Typed CSS values for interpolation in `dyn css!` and `dyn keyframes!`.
+//!
+//! Every type implements `Display` with stable rounding,
+//! so `Vw(0.1 + 0.2)` is written as `0.3vw` instead of `0.30000000000000004vw`.
+
+use std::fmt;
+
+// Write number with at most 4 fractional digits and without trailing zeros.
+//
+// eg)
+// 0.30000000000000004 -> 0.3
+// 10.0 -> 10
+// -0.00001 -> 0
+fn write_number(f: &mut fmt::Formatter, value: f64) -> fmt::Result {
+    if !value.is_finite() {
+        return f.write_str("0");
+    }
+
+    // Huge values overflow when scaled, and have no fractional digits to round anyway.
+    let scaled = value * 10000.0;
+    let rounded = if scaled.is_finite() {
+        scaled.round() / 10000.0
+    } else {
+        value
+    };
+    let number = format!("{rounded:.4}");
+    let number = number.trim_end_matches('0').trim_end_matches('.');
+    if number == "-0" {
+        f.write_str("0")
+    } else {
+        f.write_str(number)
+    }
+}
+
+/// Marker trait for length values accepted by [`Transform`].
+pub trait Length: fmt::Display {}
+
+/// Marker trait for angle values accepted by [`Transform`].
+pub trait Angle: fmt::Display {}
+
+/// Marker trait for time values.
+pub trait Time: fmt::Display {}
+
+macro_rules! unit {
+    ($(#[$meta:meta])* $name:ident, $suffix:literal $(, $marker:ident)?) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+        pub struct $name(pub f64);
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write_number(f, self.0)?;
+                f.write_str($suffix)
+            }
+        }
+        $(impl $marker for $name {})?
+    };
+}
+
+unit!(
+    /// Unitless number, e.g. for `opacity` or `line-height`.
+    Number,
+    ""
+);
+unit!(
+    /// `px` length.
+    Px,
+    "px",
+    Length
+);
+unit!(
+    /// `em` length.
+    Em,
+    "em",
+    Length
+);
+unit!(
+    /// `rem` length.
+    Rem,
+    "rem",
+    Length
+);
+unit!(
+    /// `%` length.
+    Percent,
+    "%",
+    Length
+);
+unit!(
+    /// `vw` length.
+    Vw,
+    "vw",
+    Length
+);
+unit!(
+    /// `vh` length.
+    Vh,
+    "vh",
+    Length
+);
+unit!(
+    /// `deg` angle.
+    Deg,
+    "deg",
+    Angle
+);
+unit!(
+    /// `turn` angle.
+    Turn,
+    "turn",
+    Angle
+);
+unit!(
+    /// `ms` time.
+    Ms,
+    "ms",
+    Time
+);
+unit!(
+    /// `s` time.
+    S,
+    "s",
+    Time
+);
+
+// Internal representation of color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColorValue {
+    Rgb(u8, u8, u8),
+    Rgba(u8, u8, u8, f64),
+    Hsl(f64, f64, f64),
+    Hsla(f64, f64, f64, f64),
+    Hex(u32),
+}
+
+/// CSS color.
+///
+/// ```
+/// use yew_style_in_rs::units::Color;
+///
+/// assert_eq!(Color::rgb(255, 128, 0).to_string(), "rgb(255,128,0)");
+/// assert_eq!(Color::hsl(120.0, 50.0, 25.0).to_string(), "hsl(120,50%,25%)");
+/// assert_eq!(Color::hex(0x00ff88).to_string(), "#00ff88");
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Color(ColorValue);
+impl Color {
+    /// `rgb(r,g,b)`
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self(ColorValue::Rgb(r, g, b))
+    }
+
+    /// `rgba(r,g,b,alpha)`. `alpha` is clamped to `0.0..=1.0`.
+    pub fn rgba(r: u8, g: u8, b: u8, alpha: f64) -> Self {
+        Self(ColorValue::Rgba(r, g, b, alpha.clamp(0.0, 1.0)))
+    }
+
+    /// `hsl(hue,saturation%,lightness%)`. `saturation` and `lightness` are percentages.
+    pub fn hsl(hue: f64, saturation: f64, lightness: f64) -> Self {
+        Self(ColorValue::Hsl(hue, saturation, lightness))
+    }
+
+    /// `hsla(hue,saturation%,lightness%,alpha)`. `alpha` is clamped to `0.0..=1.0`.
+    pub fn hsla(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Self {
+        Self(ColorValue::Hsla(
+            hue,
+            saturation,
+            lightness,
+            alpha.clamp(0.0, 1.0),
+        ))
+    }
+
+    /// `#rrggbb` from `0xrrggbb`. Upper bits are ignored.
+    pub fn hex(rgb: u32) -> Self {
+        Self(ColorValue::Hex(rgb & 0xffffff))
+    }
+}
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            ColorValue::Rgb(r, g, b) => write!(f, "rgb({r},{g},{b})"),
+            ColorValue::Rgba(r, g, b, a) => {
+                write!(f, "rgba({r},{g},{b},")?;
+                write_number(f, a)?;
+                f.write_str(")")
+            }
+            ColorValue::Hsl(h, s, l) => {
+                write!(f, "hsl({},{},{})", Number(h), Percent(s), Percent(l))
+            }
+            ColorValue::Hsla(h, s, l, a) => write!(
+                f,
+                "hsla({},{},{},{})",
+                Number(h),
+                Percent(s),
+                Percent(l),
+                Number(a)
+            ),
+            ColorValue::Hex(rgb) => write!(f, "#{rgb:06x}"),
+        }
+    }
+}
+
+/// Builder of `transform` property value.
+///
+/// ```
+/// use yew_style_in_rs::units::{Deg, Px, Transform};
+///
+/// let transform = Transform::new().translate_x(Px(0.1 + 0.2)).rotate(Deg(45.0));
+/// assert_eq!(transform.to_string(), "translateX(0.3px) rotate(45deg)");
+/// assert_eq!(Transform::new().to_string(), "none");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transform(Vec<String>);
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(mut self, function: String) -> Self {
+        self.0.push(function);
+        self
+    }
+
+    pub fn translate(self, x: impl Length, y: impl Length) -> Self {
+        self.push(format!("translate({x},{y})"))
+    }
+
+    pub fn translate_x(self, x: impl Length) -> Self {
+        self.push(format!("translateX({x})"))
+    }
+
+    pub fn translate_y(self, y: impl Length) -> Self {
+        self.push(format!("translateY({y})"))
+    }
+
+    pub fn scale(self, x: f64, y: f64) -> Self {
+        self.push(format!("scale({},{})", Number(x), Number(y)))
+    }
+
+    pub fn scale_x(self, x: f64) -> Self {
+        self.push(format!("scaleX({})", Number(x)))
+    }
+
+    pub fn scale_y(self, y: f64) -> Self {
+        self.push(format!("scaleY({})", Number(y)))
+    }
+
+    pub fn rotate(self, angle: impl Angle) -> Self {
+        self.push(format!("rotate({angle})"))
+    }
+
+    pub fn skew_x(self, angle: impl Angle) -> Self {
+        self.push(format!("skewX({angle})"))
+    }
+
+    pub fn skew_y(self, angle: impl Angle) -> Self {
+        self.push(format!("skewY({angle})"))
+    }
+}
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("none")
+        } else {
+            f.write_str(&self.0.join(" "))
+        }
+    }
+}
+
+// Used by `dyn css!` and `dyn keyframes!` expansion to warn raw floats interpolated next to a unit suffix.
+//
+// `UnitSuffixCheck(&value).check()` resolves to the deprecated inherent method
+// only when `value` is `f32` or `f64`, and to the no-op trait method otherwise.
+#[doc(hidden)]
+pub mod __private {
+    pub struct UnitSuffixCheck<T>(pub T);
+    impl UnitSuffixCheck<&f64> {
+        #[deprecated(
+            note = "raw float interpolated next to a unit suffix; use a type in `yew_style_in_rs::units` such as `Px` for stable rounding"
+        )]
+        pub fn check(&self) {}
+    }
+    impl UnitSuffixCheck<&f32> {
+        #[deprecated(
+            note = "raw float interpolated next to a unit suffix; use a type in `yew_style_in_rs::units` such as `Px` for stable rounding"
+        )]
+        pub fn check(&self) {}
+    }
+
+    pub trait UnitSuffixFallback {
+        fn check(&self) {}
+    }
+    impl<T> UnitSuffixFallback for UnitSuffixCheck<T> {}
+}